@@ -14,7 +14,7 @@ There currently defined features within the crate are as follows:
 
 | Feature | Description | Default Feature? |
 |---------|-------------|---------|
-| `mixed_numerics` | Should numbers be parsed separately as `i64` and `f64`? | `yerp` |
+| `mixed_numerics` | Should numbers be parsed separately as `i64` and `f64` by default? (The mode may also be selected at runtime through `ParserOptions`) | `yerp` |
 
 ### Examples
 
@@ -3,6 +3,7 @@
 
 use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
+use std::ops::Range;
 
 /// A [Coords] represents a single location within the parser input
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -13,28 +14,68 @@ pub struct Coords {
     pub line: usize,
     /// The column position
     pub column: usize,
+    /// The byte offset of the character within the input, which may be used to slice the original
+    /// byte buffer. (Offsets are relative to the encoded input, so are reported in UTF-8 bytes when
+    /// parsing a `&str`, and in terms of the source encoding otherwise)
+    pub offset: usize,
+    /// The column position in UTF-16 code units, as expected by Language Server Protocol clients
+    pub utf16_column: usize,
 }
 
 impl Coords {
     /// Increment a coordinate by 1 character in the input. Optional, bump the current line number
-    /// and reset the column coordinate to 1
+    /// and reset the column coordinate to 1. The character is assumed to occupy a single byte
     #[inline]
     pub fn inc(&mut self, newline: bool) {
+        self.offset += 1;
         if newline {
             self.absolute += 1;
             self.line += 1;
             self.column = 1;
+            self.utf16_column = 1;
         } else {
             self.absolute += 1;
             self.column += 1;
+            self.utf16_column += 1;
         }
     }
 
-    /// Increment a coordinate by n.  Does not allow for crossing newline boundaries
+    /// Increment a coordinate by n.  Does not allow for crossing newline boundaries, and assumes
+    /// that each character occupies a single byte
     #[inline]
     pub fn inc_n(&mut self, n: usize) {
         self.absolute += n;
         self.column += n;
+        self.offset += n;
+        self.utf16_column += n;
+    }
+
+    /// Move a coordinate on to a given character, which starts at a given byte offset within the
+    /// input. The character preceding it (if any) determines whether a new line has been started,
+    /// so that `\n`, `\r\n` and a lone `\r` are each treated as a single line break. Line
+    /// breaks belong to the end of the line which they terminate, and the first character of each
+    /// line is in column 1, matching the positions displayed by most editors. The UTF-16 column
+    /// is that of the first code unit of the character, so is advanced by the width of the
+    /// preceding character
+    #[inline]
+    pub fn inc_char(&mut self, ch: char, offset: usize, previous: Option<char>) {
+        self.absolute += 1;
+        self.offset = offset;
+        match (previous, ch) {
+            (Some('\r'), '\n') => {
+                self.column += 1;
+                self.utf16_column += 1;
+            }
+            (Some('\n'), _) | (Some('\r'), _) => {
+                self.line += 1;
+                self.column = 1;
+                self.utf16_column = 1;
+            }
+            _ => {
+                self.column += 1;
+                self.utf16_column += previous.map_or(1, char::len_utf16);
+            }
+        }
     }
 }
 
@@ -42,8 +83,8 @@ impl Display for Coords {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[abs: {}, line: {}, column: {}]",
-            self.absolute, self.line, self.column
+            "[abs: {}, line: {}, column: {}, offset: {}]",
+            self.absolute, self.line, self.column, self.offset
         )
     }
 }
@@ -55,6 +96,8 @@ impl Default for Coords {
             absolute: 0,
             line: 1,
             column: 0,
+            offset: 0,
+            utf16_column: 0,
         }
     }
 }
@@ -63,11 +106,7 @@ impl Eq for Coords {}
 
 impl PartialOrd<Self> for Coords {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        match self.absolute.cmp(&other.absolute) {
-            Ordering::Less => Some(Ordering::Less),
-            Ordering::Equal => Some(Ordering::Equal),
-            Ordering::Greater => Some(Ordering::Greater),
-        }
+        Some(self.cmp(other))
     }
 }
 
@@ -92,6 +131,9 @@ pub struct Span {
     pub start: Coords,
     /// End [Coords] for the span
     pub end: Coords,
+    /// The byte offset immediately following the final character of the span, in the same terms
+    /// as [Coords::offset]
+    pub end_offset: usize,
 }
 
 impl Span {
@@ -103,6 +145,12 @@ impl Span {
             Ordering::Greater => self.start - self.end,
         }
     }
+
+    /// Get the range of bytes covered by the span, which may be used to slice the raw text of a
+    /// token from the original input
+    pub fn byte_range(&self) -> Range<usize> {
+        self.start.offset..self.end_offset
+    }
 }
 
 impl Display for Span {
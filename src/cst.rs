@@ -0,0 +1,672 @@
+//! A lossless concrete syntax tree (CST) for editing JSON documents without reformatting them
+//!
+//! Unlike the DOM, the CST retains everything found within the input. Whitespace (along with
+//! anything else skipped by the [Lexer]) is held as *trivia* attached to the node which follows it,
+//! and the original lexemes of keys, strings and numbers are kept verbatim. As a result, printing
+//! a [Document] reproduces the input byte-for-byte, and edits made through a [JsonPointer] only
+//! disturb the region of the document which they touch:
+//!
+//! ```rust
+//! use chisel_json::cst::Parser;
+//! use chisel_json::pointer::JsonPointer;
+//!
+//! let input = "{\n  \"name\" : \"chisel\",\n  \"version\" : 1.0\n}\n";
+//! let mut document = Parser::default().parse_str(input).unwrap();
+//! assert_eq!(document.to_string(), input);
+//!
+//! let mut pointer = JsonPointer::default();
+//! pointer.push_name("version".to_string());
+//! document.set(&pointer, "2.0").unwrap();
+//! assert_eq!(document.to_string(), "{\n  \"name\" : \"chisel\",\n  \"version\" : 2.0\n}\n");
+//! ```
+use crate::coords::Span;
+use crate::cst_error;
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use crate::lexer::{Lexer, Token};
+use crate::options::{LimitTracker, NumericMode, ParserOptions, Separators};
+use crate::pointer::{JsonPointer, JsonPointerComponent};
+use std::fmt::{Display, Formatter};
+
+/// A node within the tree, made up of a value along with the trivia which precedes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    /// The trivia (whitespace) preceding the value
+    pub leading: String,
+    /// The value itself
+    pub value: Value,
+}
+
+/// The different kinds of value held within a [Node]
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// An object, along with the trivia preceding its closing brace
+    Object {
+        /// The members of the object
+        members: Vec<Member>,
+        /// The trivia preceding the closing brace
+        closing: String,
+    },
+    /// An array, along with the trivia preceding its closing bracket
+    Array {
+        /// The elements of the array
+        elements: Vec<Element>,
+        /// The trivia preceding the closing bracket
+        closing: String,
+    },
+    /// A string, number or literal, held as its original lexeme
+    Scalar(String),
+}
+
+/// A member of an object
+#[derive(Debug, Clone, PartialEq)]
+pub struct Member {
+    /// The trivia preceding the key
+    pub leading: String,
+    /// The original lexeme of the key, including any quotes
+    pub key: String,
+    /// The trivia preceding the colon
+    pub colon: String,
+    /// The value of the member
+    pub value: Node,
+    /// The trivia preceding the comma following the member, if there is one
+    pub comma: Option<String>,
+}
+
+/// An element of an array
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    /// The value of the element
+    pub value: Node,
+    /// The trivia preceding the comma following the element, if there is one
+    pub comma: Option<String>,
+}
+
+/// A complete document, made up of a root [Node] and any trivia following it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    /// The root of the document
+    pub root: Node,
+    /// The trivia following the root
+    pub trailing: String,
+    /// The [ParserOptions] used to parse the document, and any fragments edited into it
+    options: ParserOptions,
+}
+
+impl Member {
+    /// Check whether the member has a given (unquoted) name. Escape sequences within the key are
+    /// compared as they appear within the input
+    fn is_named(&self, name: &str) -> bool {
+        match self.key.chars().next() {
+            Some('"' | '\'') if self.key.len() >= 2 => &self.key[1..self.key.len() - 1] == name,
+            _ => self.key == name,
+        }
+    }
+}
+
+impl Display for Node {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.leading, self.value)
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Object { members, closing } => {
+                write!(f, "{{")?;
+                for member in members {
+                    write!(
+                        f,
+                        "{}{}{}:{}",
+                        member.leading, member.key, member.colon, member.value
+                    )?;
+                    if let Some(comma) = &member.comma {
+                        write!(f, "{},", comma)?;
+                    }
+                }
+                write!(f, "{}}}", closing)
+            }
+            Value::Array { elements, closing } => {
+                write!(f, "[")?;
+                for element in elements {
+                    write!(f, "{}", element.value)?;
+                    if let Some(comma) = &element.comma {
+                        write!(f, "{},", comma)?;
+                    }
+                }
+                write!(f, "{}]", closing)
+            }
+            Value::Scalar(lexeme) => write!(f, "{}", lexeme),
+        }
+    }
+}
+
+impl Display for Document {
+    /// Print the document, exactly as it was found within the input (along with any edits)
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.root, self.trailing)
+    }
+}
+
+impl Document {
+    /// Get the [Node] at a given [JsonPointer], if there is one
+    pub fn get(&self, pointer: &JsonPointer) -> Option<&Node> {
+        let mut node = &self.root;
+        for component in pointer.components() {
+            node = match (component, &node.value) {
+                (JsonPointerComponent::Root, _) => node,
+                (JsonPointerComponent::Name(name), Value::Object { members, .. }) => {
+                    &members.iter().find(|m| m.is_named(name))?.value
+                }
+                (JsonPointerComponent::Index(index), Value::Array { elements, .. }) => {
+                    &elements.get(*index)?.value
+                }
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// Replace the value at a given [JsonPointer] with the value represented by some JSON text. The
+    /// trivia preceding the existing value is retained
+    pub fn set(&mut self, pointer: &JsonPointer, json: &str) -> ParserResult<()> {
+        let value = parse_fragment(json, &self.options)?;
+        self.node_mut(pointer)?.value = value;
+        Ok(())
+    }
+
+    /// Insert a member with a given (unquoted) key into the object at a given [JsonPointer]. If
+    /// the object already has a member with the key, then its value is replaced instead. New
+    /// members are appended to the object, and copy the trivia of the current last member so
+    /// that they follow the existing layout
+    pub fn insert(&mut self, pointer: &JsonPointer, key: &str, json: &str) -> ParserResult<()> {
+        let value = parse_fragment(json, &self.options)?;
+        let (members, closing) = match &mut self.node_mut(pointer)?.value {
+            Value::Object { members, closing } => (members, closing),
+            _ => return edit_error("keys may only be inserted into objects", pointer),
+        };
+        if let Some(member) = members.iter_mut().find(|m| m.is_named(key)) {
+            member.value.value = value;
+            return Ok(());
+        }
+        let member = match members.last_mut() {
+            Some(last) => {
+                let comma = last.comma.clone();
+                last.comma.get_or_insert_with(String::new);
+                Member {
+                    leading: last.leading.clone(),
+                    key: quote(key),
+                    colon: last.colon.clone(),
+                    value: Node {
+                        leading: last.value.leading.clone(),
+                        value,
+                    },
+                    comma,
+                }
+            }
+            None => {
+                if closing.is_empty() {
+                    closing.push(' ');
+                }
+                Member {
+                    leading: String::from(" "),
+                    key: quote(key),
+                    colon: String::from(" "),
+                    value: Node {
+                        leading: String::from(" "),
+                        value,
+                    },
+                    comma: None,
+                }
+            }
+        };
+        members.push(member);
+        Ok(())
+    }
+
+    /// Remove the object member or array element at a given [JsonPointer], returning the removed
+    /// [Node]. The separating comma is removed along with the value, and the trivia preceding a
+    /// removed first member or element is handed on to its successor
+    pub fn remove(&mut self, pointer: &JsonPointer) -> ParserResult<Node> {
+        let mut parent = pointer.clone();
+        let last = match parent.pop() {
+            Some(JsonPointerComponent::Root) | None => {
+                return edit_error("the root of a document can't be removed", pointer)
+            }
+            Some(last) => last,
+        };
+        let not_found = || cst_error!(ParserErrorDetails::PointerNotFound(pointer.to_string()));
+        match (last, &mut self.node_mut(&parent)?.value) {
+            (JsonPointerComponent::Name(name), Value::Object { members, .. }) => {
+                let index = match members.iter().position(|m| m.is_named(&name)) {
+                    Some(index) => index,
+                    None => return not_found(),
+                };
+                let removed = members.remove(index);
+                if index == 0 {
+                    if let Some(next) = members.first_mut() {
+                        next.leading = removed.leading;
+                    }
+                } else if index == members.len() {
+                    members[index - 1].comma = removed.comma;
+                }
+                Ok(removed.value)
+            }
+            (JsonPointerComponent::Index(index), Value::Array { elements, .. }) => {
+                if index >= elements.len() {
+                    return not_found();
+                }
+                let mut removed = elements.remove(index);
+                if index == 0 {
+                    if let Some(next) = elements.first_mut() {
+                        std::mem::swap(&mut next.value.leading, &mut removed.value.leading);
+                    }
+                } else if index == elements.len() {
+                    elements[index - 1].comma = removed.comma;
+                }
+                Ok(removed.value)
+            }
+            _ => not_found(),
+        }
+    }
+
+    /// Get mutable access to the [Node] at a given [JsonPointer]
+    fn node_mut(&mut self, pointer: &JsonPointer) -> ParserResult<&mut Node> {
+        let mut node = &mut self.root;
+        let components = pointer
+            .components()
+            .filter(|c| **c != JsonPointerComponent::Root);
+        for component in components {
+            let current = node;
+            let child = match (component, &mut current.value) {
+                (JsonPointerComponent::Name(name), Value::Object { members, .. }) => members
+                    .iter_mut()
+                    .find(|m| m.is_named(name))
+                    .map(|m| &mut m.value),
+                (JsonPointerComponent::Index(index), Value::Array { elements, .. }) => {
+                    elements.get_mut(*index).map(|e| &mut e.value)
+                }
+                _ => None,
+            };
+            node = match child {
+                Some(child) => child,
+                None => {
+                    return cst_error!(ParserErrorDetails::PointerNotFound(pointer.to_string()))
+                }
+            };
+        }
+        Ok(node)
+    }
+}
+
+/// Main CST parser struct
+#[derive(Default)]
+pub struct Parser {
+    options: ParserOptions,
+}
+
+impl Parser {
+    /// Create a new instance of the parser configured using a given set of [ParserOptions]
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self { options }
+    }
+
+    /// Get the [ParserOptions] currently in use by the parser
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Parse a `&str` into a [Document]
+    pub fn parse_str(&self, str: &str) -> ParserResult<Document> {
+        if str.is_empty() {
+            return cst_error!(ParserErrorDetails::ZeroLengthInput);
+        }
+        let options = self.options.clone().with_numeric_mode(NumericMode::Raw);
+        let mut chars = str.chars();
+        let mut lexer = Lexer::with_options(&mut chars, &options);
+        Builder::new(str, &options).parse_document(&mut lexer)
+    }
+}
+
+/// Parse a fragment of JSON text (which may be any kind of value) into a [Value] using a given
+/// set of (raw numeric) [ParserOptions], discarding any surrounding whitespace. The fragment is
+/// terminated with a newline, since the [Lexer] treats a number running up to the end of the
+/// input as truncated
+fn parse_fragment(json: &str, options: &ParserOptions) -> ParserResult<Value> {
+    let json = format!("{}\n", json);
+    let mut chars = json.chars();
+    let mut lexer = Lexer::with_options(&mut chars, options);
+    let mut builder = Builder::new(&json, options);
+    let (_, token, span) = builder.next(&mut lexer)?;
+    let value = builder.parse_value(&mut lexer, token, span)?;
+    builder.parse_end(&mut lexer)?;
+    Ok(value)
+}
+
+/// Quote a key, escaping any characters which may not appear verbatim within a JSON string
+fn quote(key: &str) -> String {
+    let mut quoted = String::from("\"");
+    for ch in key.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Produce an [ParserErrorDetails::InvalidEdit] error for a given pointer
+fn edit_error<T>(reason: &str, pointer: &JsonPointer) -> ParserResult<T> {
+    cst_error!(ParserErrorDetails::InvalidEdit(reason.to_string()))
+        .map_err(|err: ParserError| err.with_pointer(pointer))
+}
+
+/// Builds a [Document] from the [Token]s produced by a [Lexer], recovering trivia and lexemes by
+/// slicing the input between and across the [Span]s of each token
+struct Builder<'a> {
+    /// The input being parsed
+    input: &'a str,
+    /// The [ParserOptions] in use
+    options: &'a ParserOptions,
+    /// The byte offset immediately following the last token
+    offset: usize,
+    /// The [LimitTracker] used to enforce limits on nesting
+    limits: LimitTracker,
+}
+
+impl<'a> Builder<'a> {
+    fn new(input: &'a str, options: &'a ParserOptions) -> Self {
+        Builder {
+            input,
+            options,
+            offset: 0,
+            limits: LimitTracker::new(options.limits),
+        }
+    }
+
+    /// Consume the next [Token], returning it along with the trivia which precedes it and its
+    /// [Span]
+    fn next(&mut self, lexer: &mut Lexer) -> ParserResult<(String, Token, Span)> {
+        let (token, span) = lexer.consume()?;
+        Ok(self.take_trivia(token, span))
+    }
+
+    /// As [Builder::next], for a [Token] in the position of an object key
+    fn next_key(&mut self, lexer: &mut Lexer) -> ParserResult<(String, Token, Span)> {
+        let (token, span) = lexer.consume_key()?;
+        Ok(self.take_trivia(token, span))
+    }
+
+    /// Take the trivia which precedes a [Token] with a given [Span], moving past the token
+    fn take_trivia(&mut self, token: Token, span: Span) -> (String, Token, Span) {
+        if token == Token::EndOfInput {
+            let trivia = self.input[self.offset..].to_string();
+            self.offset = self.input.len();
+            return (trivia, token, span);
+        }
+        let range = span.byte_range();
+        let trivia = self.input[self.offset..range.start].to_string();
+        self.offset = range.end;
+        (trivia, token, span)
+    }
+
+    /// Get the original lexeme of the token with a given [Span]
+    fn lexeme(&self, span: Span) -> String {
+        self.input[span.byte_range()].to_string()
+    }
+
+    /// Check a [LimitTracker] outcome, converting any failure into an error
+    fn check_limit(outcome: Result<(), ParserErrorDetails>, span: Span) -> ParserResult<()> {
+        match outcome {
+            Ok(()) => Ok(()),
+            Err(details) => cst_error!(details, span.start),
+        }
+    }
+
+    fn parse_document(&mut self, lexer: &mut Lexer) -> ParserResult<Document> {
+        let (leading, token, span) = self.next(lexer)?;
+        let value = match token {
+            Token::StartObject | Token::StartArray => self.parse_value(lexer, token, span)?,
+            _ => return cst_error!(ParserErrorDetails::InvalidRootObject, span.start),
+        };
+        let trailing = self.parse_end(lexer)?;
+        Ok(Document {
+            root: Node { leading, value },
+            trailing,
+            options: self.options.clone(),
+        })
+    }
+
+    /// Check that the end of the input has been reached, returning any trailing trivia
+    fn parse_end(&mut self, lexer: &mut Lexer) -> ParserResult<String> {
+        match self.next(lexer)? {
+            (trailing, Token::EndOfInput, _) => Ok(trailing),
+            (_, token, span) => cst_error!(ParserErrorDetails::UnexpectedToken(token), span.start),
+        }
+    }
+
+    /// Parse a [Value], given its first [Token]
+    fn parse_value(&mut self, lexer: &mut Lexer, token: Token, span: Span) -> ParserResult<Value> {
+        Self::check_limit(self.limits.node(), span)?;
+        match token {
+            Token::StartObject => self.parse_object(lexer, span),
+            Token::StartArray => self.parse_array(lexer, span),
+            Token::Str(_)
+            | Token::Float(_)
+            | Token::Integer(_)
+            | Token::RawNumber(_)
+            | Token::Boolean(_)
+            | Token::Null => Ok(Value::Scalar(self.lexeme(span))),
+            token => cst_error!(ParserErrorDetails::UnexpectedToken(token), span.start),
+        }
+    }
+
+    /// Parse a [Node], consisting of a value and its leading trivia
+    fn parse_node(&mut self, lexer: &mut Lexer) -> ParserResult<Node> {
+        let (leading, token, span) = self.next(lexer)?;
+        let value = self.parse_value(lexer, token, span)?;
+        Ok(Node { leading, value })
+    }
+
+    fn parse_object(&mut self, lexer: &mut Lexer, start: Span) -> ParserResult<Value> {
+        Self::check_limit(self.limits.enter(), start)?;
+        let mut members = vec![];
+        let mut separators = Separators::new(self.options, ParserErrorSource::Cst);
+        let closing = loop {
+            let (leading, token, span) = self.next_key(lexer)?;
+            let key = match token {
+                Token::EndObject => {
+                    separators.end()?;
+                    break leading;
+                }
+                Token::Str(_) | Token::Identifier(_) => self.lexeme(span),
+                _ => return cst_error!(ParserErrorDetails::PairExpected, span.start),
+            };
+            separators.member(&token, span)?;
+            let colon = match self.next(lexer)? {
+                (colon, Token::Colon, _) => colon,
+                (_, _, span) => return cst_error!(ParserErrorDetails::PairExpected, span.start),
+            };
+            let value = self.parse_node(lexer)?;
+            let (trivia, token, span) = self.next(lexer)?;
+            match token {
+                Token::Comma => {
+                    separators.comma(span)?;
+                    members.push(Member {
+                        leading,
+                        key,
+                        colon,
+                        value,
+                        comma: Some(trivia),
+                    })
+                }
+                Token::EndObject => {
+                    members.push(Member {
+                        leading,
+                        key,
+                        colon,
+                        value,
+                        comma: None,
+                    });
+                    break trivia;
+                }
+                token => return cst_error!(ParserErrorDetails::UnexpectedToken(token), span.start),
+            }
+        };
+        self.limits.exit();
+        Ok(Value::Object { members, closing })
+    }
+
+    fn parse_array(&mut self, lexer: &mut Lexer, start: Span) -> ParserResult<Value> {
+        Self::check_limit(self.limits.enter(), start)?;
+        let mut elements = vec![];
+        let mut separators = Separators::new(self.options, ParserErrorSource::Cst);
+        let closing = loop {
+            let (leading, token, span) = self.next(lexer)?;
+            if token == Token::EndArray {
+                separators.end()?;
+                break leading;
+            }
+            separators.member(&token, span)?;
+            let value = Node {
+                leading,
+                value: self.parse_value(lexer, token, span)?,
+            };
+            let (trivia, token, span) = self.next(lexer)?;
+            match token {
+                Token::Comma => {
+                    separators.comma(span)?;
+                    elements.push(Element {
+                        value,
+                        comma: Some(trivia),
+                    })
+                }
+                Token::EndArray => {
+                    elements.push(Element { value, comma: None });
+                    break trivia;
+                }
+                token => return cst_error!(ParserErrorDetails::UnexpectedToken(token), span.start),
+            }
+        };
+        self.limits.exit();
+        Ok(Value::Array { elements, closing })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::Parser;
+    use crate::errors::ParserErrorDetails;
+    use crate::options::{CommentPolicy, Dialect, ParserOptions};
+    use crate::pointer::JsonPointer;
+    use std::fs;
+
+    /// A document with deliberately irregular formatting
+    const INPUT: &str = concat!(
+        "{\r\n\t\"name\":\"chisel\" ,\n",
+        "  \"tags\"  : [ 1.50e+3,\"a\\u0041\" , null ],\n",
+        "  \"nested\": {}\n}  \n"
+    );
+
+    #[test]
+    fn should_print_fixtures_byte_for_byte() {
+        for f in fs::read_dir("fixtures/json/valid").unwrap() {
+            let path = f.unwrap().path();
+            let input = fs::read_to_string(&path).unwrap();
+            let document = Parser::default().parse_str(&input).unwrap();
+            assert_eq!(document.to_string(), input, "{:?}", path);
+        }
+        let document = Parser::default().parse_str(INPUT).unwrap();
+        assert_eq!(document.to_string(), INPUT);
+        let input = fs::read_to_string("fixtures/json/jsonc/settings.jsonc").unwrap();
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Skip);
+        let document = Parser::with_options(options).parse_str(&input).unwrap();
+        assert_eq!(document.to_string(), input);
+    }
+
+    #[test]
+    fn should_only_disturb_edited_regions() {
+        let mut document = Parser::default().parse_str(INPUT).unwrap();
+        let mut tags = JsonPointer::default();
+        tags.push_name("tags".to_string());
+        let mut first = tags.clone();
+        first.push_index(0);
+        document.set(&first, " 7 ").unwrap();
+        document
+            .remove(&{
+                let mut last = tags.clone();
+                last.push_index(2);
+                last
+            })
+            .unwrap();
+        document
+            .insert(&JsonPointer::default(), "new\"key", "[]")
+            .unwrap();
+        let mut nested = JsonPointer::default();
+        nested.push_name("nested".to_string());
+        document.insert(&nested, "x", "true").unwrap();
+        let mut name = JsonPointer::default();
+        name.push_name("name".to_string());
+        document.remove(&name).unwrap();
+        assert_eq!(
+            document.to_string(),
+            "{\r\n\t\"tags\"  : [ 7,\"a\\u0041\" ],\n  \"nested\": { \"x\" : true },\n  \"new\\\"key\": []\n}  \n"
+        );
+    }
+
+    #[test]
+    fn should_reject_invalid_input_and_edits() {
+        let parser = Parser::default();
+        for input in ["[1,]", "[1 2]", "{\"a\" 1}", "{} []", "1", ""] {
+            assert!(parser.parse_str(input).is_err(), "{}", input);
+        }
+        let mut document = parser.parse_str("{\"a\" : [1]}").unwrap();
+        let mut missing = JsonPointer::default();
+        missing.push_name("b".to_string());
+        let err = document.set(&missing, "1").unwrap_err();
+        assert!(matches!(
+            err.details,
+            ParserErrorDetails::PointerNotFound(_)
+        ));
+        let mut array = JsonPointer::default();
+        array.push_name("a".to_string());
+        let err = document.insert(&array, "b", "1").unwrap_err();
+        assert!(matches!(err.details, ParserErrorDetails::InvalidEdit(_)));
+        assert!(document.set(&array, "[1,").is_err());
+        assert!(document.remove(&JsonPointer::default()).is_err());
+    }
+
+    #[test]
+    fn should_preserve_json5_syntax_through_edits() {
+        let input = fs::read_to_string("fixtures/json/json5/config.json5").unwrap();
+        let parser = Parser::with_options(ParserOptions::default().with_dialect(Dialect::Json5));
+        let mut document = parser.parse_str(&input).unwrap();
+        assert_eq!(document.to_string(), input);
+        let mut mask = JsonPointer::default();
+        mask.push_name("mask".to_string());
+        document.set(&mask, "0x0F").unwrap();
+        let mut name = JsonPointer::default();
+        name.push_name("name".to_string());
+        assert_eq!(document.get(&name).unwrap().to_string(), " 'chisel'");
+        assert_eq!(
+            document.to_string(),
+            input.replace("mask: 0xFF", "mask: 0x0F")
+        );
+    }
+
+    #[test]
+    fn should_slice_tokens_ending_in_non_ascii_characters() {
+        let input = "{café: 1, \"naïve\" : 'crème', ünïcödé: [true]}";
+        let parser = Parser::with_options(ParserOptions::default().with_dialect(Dialect::Json5));
+        let mut document = parser.parse_str(input).unwrap();
+        assert_eq!(document.to_string(), input);
+        let mut key = JsonPointer::default();
+        key.push_name("café".to_string());
+        assert_eq!(document.get(&key).unwrap().to_string(), " 1");
+        document.set(&key, "2").unwrap();
+        assert_eq!(document.to_string(), input.replace("café: 1", "café: 2"));
+    }
+}
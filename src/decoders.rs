@@ -1,17 +1,53 @@
 //! Both the DOM and SAX parser implementations operate over a stream of `char`s produced by some
-//! flavour of iterator. By default, this iterator is based on a decoder that will take a stream of
-//! bytes from an underlying source, and convert into a stream of `char`s.
+//! flavour of [Decoder]. By default, a decoder will take a stream of bytes from an underlying
+//! source, and convert into a stream of `char`s.
 //!
-//! The [DecoderSelector] implemented within this module is used to instantiate new `char`
-//! iterators, based on different encodings. (Currently only ASCII and UTF-8 are supported).
-use chisel_decoders::{ascii::AsciiDecoder, utf8::Utf8Decoder};
-use std::io::BufRead;
+//! The [DecoderSelector] implemented within this module is used to instantiate new decoders,
+//! based on different encodings. (Currently ASCII, UTF-8, UTF-16, UTF-32, Latin-1 and
+//! Windows-1252 are supported). Where the encoding of the input isn't known up front,
+//! [Encoding::Auto] may be used in order to detect the encoding from the leading bytes of the
+//! input. (See [detect_encoding]).
+use crate::coords::Coords;
+use crate::decoder_error;
+use crate::decoders::ascii::AsciiDecoder;
+use crate::decoders::latin1::Latin1Decoder;
+use crate::decoders::utf16::Utf16Decoder;
+use crate::decoders::utf32::Utf32Decoder;
+use crate::decoders::utf8::Utf8Decoder;
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, Cursor, Read};
+
+pub mod ascii;
+pub mod latin1;
+pub mod utf16;
+pub mod utf32;
+pub mod utf8;
 
 /// Enumeration of different supported encoding types
-#[derive(Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Encoding {
+    /// UTF-8
     Utf8,
+    /// UTF-8, with any invalid sequences replaced by U+FFFD rather than treated as an error. (See
+    /// [Replacements])
+    Utf8Lossy,
+    /// 7-bit ASCII
     Ascii,
+    /// UTF-16, little endian
+    Utf16Le,
+    /// UTF-16, big endian
+    Utf16Be,
+    /// UTF-32, little endian
+    Utf32Le,
+    /// UTF-32, big endian
+    Utf32Be,
+    /// ISO-8859-1
+    Latin1,
+    /// Windows-1252, (a superset of the printable characters within ISO-8859-1)
+    Windows1252,
+    /// Detect the encoding from the leading bytes of the input, using [detect_encoding]
+    Auto,
 }
 
 impl Default for Encoding {
@@ -20,30 +56,429 @@ impl Default for Encoding {
     }
 }
 
-/// A struct that is essentially a factory for creating new instances of [char] iterators,
-/// based on a specified encoding type
+impl Encoding {
+    /// Whether each ASCII character is encoded as the single matching byte, and no other
+    /// character's encoding contains an ASCII byte. Input in such an encoding may safely be split
+    /// on ASCII bytes (such as line feeds) before it's decoded
+    pub fn is_ascii_compatible(&self) -> bool {
+        matches!(
+            self,
+            Encoding::Utf8
+                | Encoding::Utf8Lossy
+                | Encoding::Ascii
+                | Encoding::Latin1
+                | Encoding::Windows1252
+        )
+    }
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encoding::Utf8 => write!(f, "UTF-8"),
+            Encoding::Utf8Lossy => write!(f, "UTF-8 (lossy)"),
+            Encoding::Ascii => write!(f, "ASCII"),
+            Encoding::Utf16Le => write!(f, "UTF-16LE"),
+            Encoding::Utf16Be => write!(f, "UTF-16BE"),
+            Encoding::Utf32Le => write!(f, "UTF-32LE"),
+            Encoding::Utf32Be => write!(f, "UTF-32BE"),
+            Encoding::Latin1 => write!(f, "ISO-8859-1"),
+            Encoding::Windows1252 => write!(f, "Windows-1252"),
+            Encoding::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// A source of decoded `char`s for the lexer
+pub trait Decoder {
+    /// Decode the next `char` from the input. `None` is returned once the end of the input has
+    /// been reached, or if decoding fails
+    fn next_char(&mut self) -> Option<char>;
+
+    /// Take the error (if any) which caused decoding to stop
+    fn take_error(&mut self) -> Option<ParserError>;
+
+    /// The number of bytes of input consumed so far, which is the byte offset of the next `char` to
+    /// be decoded
+    fn offset(&self) -> usize;
+
+    /// The number of invalid sequences which have been replaced with U+FFFD so far. Only lossy
+    /// decoders make replacements
+    fn replacements(&self) -> usize {
+        0
+    }
+
+    /// The [Encoding] of the bytes being decoded, or `None` if the decoder doesn't read bytes
+    fn source_encoding(&self) -> Option<Encoding> {
+        None
+    }
+}
+
+impl<D: Decoder + ?Sized> Decoder for &mut D {
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        (**self).next_char()
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        (**self).take_error()
+    }
+
+    fn offset(&self) -> usize {
+        (**self).offset()
+    }
+
+    fn replacements(&self) -> usize {
+        (**self).replacements()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        (**self).source_encoding()
+    }
+}
+
+impl<D: Decoder + ?Sized> Decoder for Box<D> {
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        (**self).next_char()
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        (**self).take_error()
+    }
+
+    fn offset(&self) -> usize {
+        (**self).offset()
+    }
+
+    fn replacements(&self) -> usize {
+        (**self).replacements()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        (**self).source_encoding()
+    }
+}
+
+/// A [Decoder] which wraps a plain iterator of `char`s, and never fails
+pub struct Chars<I: Iterator<Item = char>> {
+    /// The underlying iterator
+    chars: I,
+    /// The length of the `char`s produced so far, when encoded as UTF-8
+    offset: usize,
+}
+
+impl<I: Iterator<Item = char>> Chars<I> {
+    /// Wrap an existing iterator of `char`s
+    pub fn new(chars: I) -> Self {
+        Chars { chars, offset: 0 }
+    }
+}
+
+impl<I: Iterator<Item = char>> Decoder for Chars<I> {
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        let ch = self.chars.next()?;
+        self.offset += ch.len_utf8();
+        Some(ch)
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        None
+    }
+
+    /// Offsets are reported as though the `char`s were encoded as UTF-8, so that they match the
+    /// byte offsets within the `&str` from which the `char`s are usually drawn
+    fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+/// Wraps a [Decoder] whose input has had a prefix (such as a byte-order mark) stripped, so that
+/// offsets remain relative to the start of the original input
+struct Skipped<D: Decoder> {
+    /// The underlying decoder
+    decoder: D,
+    /// The number of bytes stripped from the input
+    skipped: usize,
+}
+
+impl<D: Decoder> Decoder for Skipped<D> {
+    #[inline]
+    fn next_char(&mut self) -> Option<char> {
+        self.decoder.next_char()
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.decoder.take_error()
+    }
+
+    fn offset(&self) -> usize {
+        self.skipped + self.decoder.offset()
+    }
+
+    fn replacements(&self) -> usize {
+        self.decoder.replacements()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        self.decoder.source_encoding()
+    }
+}
+
+/// Reads individual bytes from an underlying [BufRead], whilst tracking the current byte offset
+pub(crate) struct ByteReader<B: BufRead> {
+    /// The underlying input
+    input: B,
+    /// The offset of the next byte to be read
+    offset: usize,
+}
+
+impl<B: BufRead> ByteReader<B> {
+    /// Create a new reader over a given input
+    pub fn new(input: B) -> Self {
+        ByteReader { input, offset: 0 }
+    }
+
+    /// The offset of the next byte to be read
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Read the next byte, returning `None` at the end of the input
+    #[inline]
+    pub fn next_byte(&mut self) -> ParserResult<Option<u8>> {
+        let byte = match self.input.fill_buf() {
+            Ok([]) => return Ok(None),
+            Ok(buffer) => buffer[0],
+            Err(err) => return decoder_error!(ParserErrorDetails::StreamFailure, cause = err),
+        };
+        self.input.consume(1);
+        self.offset += 1;
+        Ok(Some(byte))
+    }
+
+    /// Peek at the next byte without consuming it, returning `None` at the end of the input
+    #[inline]
+    pub fn peek_byte(&mut self) -> ParserResult<Option<u8>> {
+        match self.input.fill_buf() {
+            Ok(buffer) => Ok(buffer.first().copied()),
+            Err(err) => decoder_error!(ParserErrorDetails::StreamFailure, cause = err),
+        }
+    }
+
+    /// Read exactly `N` bytes. Returns `None` if the end of the input is reached before any bytes
+    /// are read, and an error if the end of the input is reached part way through
+    #[inline]
+    pub fn next_bytes<const N: usize>(
+        &mut self,
+        encoding: Encoding,
+    ) -> ParserResult<Option<[u8; N]>> {
+        let start = self.offset;
+        let mut bytes = [0u8; N];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            match self.next_byte()? {
+                Some(b) => *byte = b,
+                None if i == 0 => return Ok(None),
+                None => {
+                    return decoder_error!(ParserErrorDetails::InvalidByteSequence(encoding, start))
+                }
+            }
+        }
+        Ok(Some(bytes))
+    }
+}
+
+/// A summary of the replacements made by a lossy decoder during a parse
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Replacements {
+    /// The total number of invalid sequences replaced with U+FFFD
+    pub count: usize,
+    /// The [Coords] of the first replacement
+    pub first: Coords,
+}
+
+/// A struct that is essentially a factory for creating new instances of [Decoder]s, based on a
+/// specified encoding type
 #[derive(Default)]
 pub(crate) struct DecoderSelector {}
 
 impl DecoderSelector {
-    /// Create and return an instance of the default byte decoder / char iterator. (Utf-8)
-    pub fn default_decoder<'a, Buffer: BufRead>(
-        &'a self,
-        buffer: &'a mut Buffer,
-    ) -> Box<dyn Iterator<Item = char> + 'a> {
-        Box::new(Utf8Decoder::new(buffer))
-    }
-
-    /// Create and return an instance of a given byte decoder / char iterator based on a specific
-    /// encoding
-    pub fn new_decoder<'a, Buffer: BufRead>(
+    /// Create and return an instance of a given [Decoder] based on a specific encoding
+    pub fn new_decoder<'a, Buffer: BufRead + 'a>(
         &'a self,
-        buffer: &'a mut Buffer,
+        mut buffer: Buffer,
         encoding: Encoding,
-    ) -> Box<dyn Iterator<Item = char> + 'a> {
+    ) -> Box<dyn Decoder + 'a> {
         match encoding {
-            Encoding::Ascii => Box::new(AsciiDecoder::new(buffer)),
-            Encoding::Utf8 => Box::new(Utf8Decoder::new(buffer)),
+            Encoding::Auto => {
+                let lead = read_lead(&mut buffer);
+                let (encoding, skipped) = classify(&lead);
+                let mut lead = Cursor::new(lead);
+                lead.set_position(skipped as u64);
+                match skipped {
+                    0 => decoder_for(lead.chain(buffer), encoding),
+                    _ => Box::new(Skipped {
+                        decoder: decoder_for(lead.chain(buffer), encoding),
+                        skipped,
+                    }),
+                }
+            }
+            encoding => decoder_for(buffer, encoding),
+        }
+    }
+}
+
+/// Create a [Decoder] for a known encoding. ([Encoding::Auto] is treated as UTF-8)
+fn decoder_for<'a, Buffer: BufRead + 'a>(
+    buffer: Buffer,
+    encoding: Encoding,
+) -> Box<dyn Decoder + 'a> {
+    match encoding {
+        Encoding::Ascii => Box::new(AsciiDecoder::new(buffer)),
+        Encoding::Utf8 | Encoding::Auto => Box::new(Utf8Decoder::new(buffer, false)),
+        Encoding::Utf8Lossy => Box::new(Utf8Decoder::new(buffer, true)),
+        Encoding::Utf16Le => Box::new(Utf16Decoder::new(buffer, false)),
+        Encoding::Utf16Be => Box::new(Utf16Decoder::new(buffer, true)),
+        Encoding::Utf32Le => Box::new(Utf32Decoder::new(buffer, false)),
+        Encoding::Utf32Be => Box::new(Utf32Decoder::new(buffer, true)),
+        Encoding::Latin1 => Box::new(Latin1Decoder::new(buffer, false)),
+        Encoding::Windows1252 => Box::new(Latin1Decoder::new(buffer, true)),
+    }
+}
+
+/// Detect the encoding of a buffer from its leading bytes, returning the detected [Encoding].
+///
+/// If the input starts with a byte-order mark, then the mark determines the encoding and is
+/// consumed from the buffer. Otherwise, the pattern of null bytes within the first four bytes is
+/// used to distinguish between UTF-8, UTF-16 and UTF-32 as described in RFC 4627, (a JSON text
+/// always starts with two ASCII characters). Anything which isn't recognised is assumed to be
+/// UTF-8.
+///
+/// So that nothing other than a byte-order mark is consumed, only the bytes currently available
+/// within the buffer are examined. [Encoding::Auto] doesn't have this restriction, and reads on
+/// until it has four bytes (or reaches the end of the input) before deciding
+pub fn detect_encoding<Buffer: BufRead>(buffer: &mut Buffer) -> Encoding {
+    let (encoding, bom_length) = match buffer.fill_buf() {
+        Ok(bytes) => classify(bytes),
+        Err(_) => (Encoding::Utf8, 0),
+    };
+    buffer.consume(bom_length);
+    encoding
+}
+
+/// Consume up to the first four bytes of a buffer, reading across as many fills of the buffer as
+/// it takes. Fewer bytes are only returned if the end of the input (or an error) is reached, in
+/// which case the error is left for the decoder to find
+fn read_lead<Buffer: BufRead>(buffer: &mut Buffer) -> Vec<u8> {
+    let mut lead = Vec::with_capacity(4);
+    while lead.len() < 4 {
+        let length = match buffer.fill_buf() {
+            Ok([]) | Err(_) => break,
+            Ok(bytes) => {
+                let length = bytes.len().min(4 - lead.len());
+                lead.extend_from_slice(&bytes[..length]);
+                length
+            }
+        };
+        buffer.consume(length);
+    }
+    lead
+}
+
+/// Classify the leading bytes of the input, returning the detected [Encoding] along with the
+/// length of any byte-order mark
+fn classify(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xef, 0xbb, 0xbf, ..] => (Encoding::Utf8, 3),
+        [0x00, 0x00, 0xfe, 0xff, ..] => (Encoding::Utf32Be, 4),
+        [0xff, 0xfe, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+        [0xfe, 0xff, ..] => (Encoding::Utf16Be, 2),
+        [0xff, 0xfe, ..] => (Encoding::Utf16Le, 2),
+        [0x00, 0x00, 0x00, _, ..] => (Encoding::Utf32Be, 0),
+        [_, 0x00, 0x00, 0x00, ..] => (Encoding::Utf32Le, 0),
+        [0x00, _, 0x00, _, ..] => (Encoding::Utf16Be, 0),
+        [_, 0x00, _, 0x00, ..] => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::{detect_encoding, Decoder, DecoderSelector, Encoding};
+    use std::io::{BufRead, BufReader};
+
+    #[test]
+    fn should_detect_and_strip_byte_order_marks() {
+        for (bytes, encoding, remaining) in [
+            (&[0xef, 0xbb, 0xbf, b'[', b']'][..], Encoding::Utf8, 2),
+            (&[0xfe, 0xff, 0x00, b'['][..], Encoding::Utf16Be, 2),
+            (&[0xff, 0xfe, b'[', 0x00][..], Encoding::Utf16Le, 2),
+            (
+                &[0x00, 0x00, 0xfe, 0xff, 0x00, 0x00, 0x00, b'['][..],
+                Encoding::Utf32Be,
+                4,
+            ),
+            (
+                &[0xff, 0xfe, 0x00, 0x00, b'[', 0x00, 0x00, 0x00][..],
+                Encoding::Utf32Le,
+                4,
+            ),
+        ] {
+            let mut reader = BufReader::new(bytes);
+            assert_eq!(detect_encoding(&mut reader), encoding);
+            assert_eq!(reader.fill_buf().unwrap().len(), remaining);
+        }
+    }
+
+    #[test]
+    fn should_detect_encoding_from_null_byte_patterns() {
+        for (bytes, encoding) in [
+            (&[b'[', b'1', b']'][..], Encoding::Utf8),
+            (&[0x00, b'[', 0x00, b'1'][..], Encoding::Utf16Be),
+            (&[b'[', 0x00, b'1', 0x00][..], Encoding::Utf16Le),
+            (&[0x00, 0x00, 0x00, b'['][..], Encoding::Utf32Be),
+            (&[b'[', 0x00, 0x00, 0x00][..], Encoding::Utf32Le),
+            (&[][..], Encoding::Utf8),
+        ] {
+            let mut reader = BufReader::new(bytes);
+            assert_eq!(detect_encoding(&mut reader), encoding);
+            assert_eq!(reader.fill_buf().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn auto_decoder_should_decode_detected_encoding() {
+        let bytes: Vec<u8> = [0xfeff, '[' as u16, 0x00e9, ']' as u16]
+            .iter()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        let selector = DecoderSelector::default();
+        let mut reader = BufReader::new(bytes.as_slice());
+        let mut decoder = selector.new_decoder(&mut reader, Encoding::Auto);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
+        }
+        assert_eq!(decoded, "[\u{e9}]");
+    }
+
+    #[test]
+    fn auto_decoder_should_read_across_short_buffers() {
+        let bytes: Vec<u8> = "[\"\u{e9}\"]"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        let selector = DecoderSelector::default();
+        let mut reader = BufReader::with_capacity(1, bytes.as_slice());
+        let mut decoder = selector.new_decoder(&mut reader, Encoding::Auto);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
         }
+        assert_eq!(decoded, "[\"\u{e9}\"]");
+        assert_eq!(decoder.source_encoding(), Some(Encoding::Utf16Be));
+        assert_eq!(decoder.offset(), bytes.len());
     }
 }
@@ -0,0 +1,87 @@
+//! A decoder implementation that will take an underlying byte source and produce a stream of
+//! `char`s decoded from 7-bit ASCII
+use crate::decoder_error;
+use crate::decoders::{ByteReader, Decoder, Encoding};
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::io::BufRead;
+
+/// An ASCII decoder, which reads from a [BufRead] instance. Any byte with the high bit set
+/// halts decoding
+pub struct AsciiDecoder<B: BufRead> {
+    /// The underlying input
+    bytes: ByteReader<B>,
+    /// Any error which has halted decoding
+    error: Option<ParserError>,
+}
+
+impl<B: BufRead> AsciiDecoder<B> {
+    /// Create a new decoder
+    pub fn new(input: B) -> Self {
+        AsciiDecoder {
+            bytes: ByteReader::new(input),
+            error: None,
+        }
+    }
+
+    /// Attempt to decode the next character
+    fn decode_next(&mut self) -> ParserResult<Option<char>> {
+        let start = self.bytes.offset();
+        match self.bytes.next_byte()? {
+            Some(byte) if byte.is_ascii() => Ok(Some(byte as char)),
+            Some(_) => decoder_error!(ParserErrorDetails::InvalidByteSequence(
+                Encoding::Ascii,
+                start
+            )),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<B: BufRead> Decoder for AsciiDecoder<B> {
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode_next() {
+            Ok(ch) => ch,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.error.take()
+    }
+
+    fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        Some(Encoding::Ascii)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::ascii::AsciiDecoder;
+    use crate::decoders::{Decoder, Encoding};
+    use crate::errors::ParserErrorDetails;
+    use std::io::BufReader;
+
+    #[test]
+    fn should_reject_non_ascii_bytes() {
+        let bytes = "[\"é\"]".as_bytes();
+        let mut reader = BufReader::new(bytes);
+        let mut decoder = AsciiDecoder::new(&mut reader);
+        assert_eq!(decoder.next_char(), Some('['));
+        assert_eq!(decoder.next_char(), Some('"'));
+        assert_eq!(decoder.next_char(), None);
+        assert_eq!(
+            decoder.take_error().unwrap().details,
+            ParserErrorDetails::InvalidByteSequence(Encoding::Ascii, 2)
+        );
+    }
+}
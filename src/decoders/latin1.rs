@@ -0,0 +1,118 @@
+//! A decoder implementation that will take an underlying byte source and produce a stream of
+//! `char`s decoded from either ISO-8859-1 (Latin-1) or Windows-1252
+use crate::decoders::{ByteReader, Decoder, Encoding};
+use crate::errors::ParserError;
+use std::io::BufRead;
+
+/// Mappings for the bytes 0x80..=0x9f within Windows-1252. The five bytes which are undefined
+/// within Windows-1252 map onto the equivalent C1 control characters, so that the mapping is
+/// lossless
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20ac}', '\u{0081}', '\u{201a}', '\u{0192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02c6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008d}', '\u{017d}', '\u{008f}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02dc}', '\u{2122}', '\u{0161}', '\u{203a}', '\u{0153}', '\u{009d}', '\u{017e}', '\u{0178}',
+];
+
+/// A single-byte decoder for Latin-1 and Windows-1252, which reads from a [BufRead] instance.
+/// Every byte maps onto a single `char`, so decoding can only fail if the underlying stream does
+pub struct Latin1Decoder<B: BufRead> {
+    /// The underlying input
+    bytes: ByteReader<B>,
+    /// Whether to apply the Windows-1252 mappings for the bytes 0x80..=0x9f
+    windows_1252: bool,
+    /// Any error which has halted decoding
+    error: Option<ParserError>,
+}
+
+impl<B: BufRead> Latin1Decoder<B> {
+    /// Create a new decoder, specifying whether the input is Windows-1252 rather than Latin-1
+    pub fn new(input: B, windows_1252: bool) -> Self {
+        Latin1Decoder {
+            bytes: ByteReader::new(input),
+            windows_1252,
+            error: None,
+        }
+    }
+
+    /// Map a single byte onto a `char`
+    #[inline]
+    fn map(&self, byte: u8) -> char {
+        match byte {
+            0x80..=0x9f if self.windows_1252 => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+            _ => byte as char,
+        }
+    }
+}
+
+impl<B: BufRead> Decoder for Latin1Decoder<B> {
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.bytes.next_byte() {
+            Ok(byte) => byte.map(|b| self.map(b)),
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.error.take()
+    }
+
+    fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        if self.windows_1252 {
+            Some(Encoding::Windows1252)
+        } else {
+            Some(Encoding::Latin1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::latin1::Latin1Decoder;
+    use crate::decoders::Decoder;
+    use std::io::BufReader;
+
+    fn decode(bytes: &[u8], windows_1252: bool) -> String {
+        let mut reader = BufReader::new(bytes);
+        let mut decoder = Latin1Decoder::new(&mut reader, windows_1252);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
+        }
+        assert!(decoder.take_error().is_none());
+        decoded
+    }
+
+    #[test]
+    fn should_decode_latin1() {
+        let bytes = [b'"', b'n', b'a', 0xef, b'v', b'e', b' ', 0x80, 0xff, b'"'];
+        assert_eq!(decode(&bytes, false), "\"naïve \u{80}ÿ\"");
+    }
+
+    #[test]
+    fn should_decode_windows_1252() {
+        let bytes = [0x80, 0x93, b'x', 0x94, 0x81, 0xe9];
+        assert_eq!(decode(&bytes, true), "€“x”\u{81}é");
+    }
+
+    #[test]
+    fn every_byte_should_map_to_a_distinct_char() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for windows_1252 in [false, true] {
+            let mut decoded: Vec<char> = decode(&bytes, windows_1252).chars().collect();
+            decoded.sort();
+            decoded.dedup();
+            assert_eq!(decoded.len(), 256);
+        }
+    }
+}
@@ -0,0 +1,186 @@
+//! A decoder implementation that will take an underlying byte source and produce a stream of
+//! `char`s decoded from either little or big endian UTF-16
+use crate::decoder_error;
+use crate::decoders::{ByteReader, Decoder, Encoding};
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::io::BufRead;
+
+/// Range of values for the leading (high) half of a surrogate pair
+const HIGH_SURROGATES: std::ops::RangeInclusive<u16> = 0xd800..=0xdbff;
+/// Range of values for the trailing (low) half of a surrogate pair
+const LOW_SURROGATES: std::ops::RangeInclusive<u16> = 0xdc00..=0xdfff;
+
+/// A UTF-16 decoder, which reads from a [BufRead] instance.
+pub struct Utf16Decoder<B: BufRead> {
+    /// The underlying input
+    bytes: ByteReader<B>,
+    /// Whether code units are big endian
+    big_endian: bool,
+    /// Any error which has halted decoding
+    error: Option<ParserError>,
+}
+
+impl<B: BufRead> Utf16Decoder<B> {
+    /// Create a new decoder, specifying the byte order of the input
+    pub fn new(input: B, big_endian: bool) -> Self {
+        Utf16Decoder {
+            bytes: ByteReader::new(input),
+            big_endian,
+            error: None,
+        }
+    }
+
+    /// The [Encoding] being decoded
+    fn encoding(&self) -> Encoding {
+        if self.big_endian {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf16Le
+        }
+    }
+
+    /// Read the next code unit from the input
+    #[inline]
+    fn next_unit(&mut self) -> ParserResult<Option<u16>> {
+        Ok(self
+            .bytes
+            .next_bytes::<2>(self.encoding())?
+            .map(|bytes| match self.big_endian {
+                true => u16::from_be_bytes(bytes),
+                false => u16::from_le_bytes(bytes),
+            }))
+    }
+
+    /// Attempt to decode the next character, combining surrogate pairs where required
+    fn decode_next(&mut self) -> ParserResult<Option<char>> {
+        let start = self.bytes.offset();
+        match self.next_unit()? {
+            Some(high) if HIGH_SURROGATES.contains(&high) => match self.next_unit()? {
+                Some(low) if LOW_SURROGATES.contains(&low) => {
+                    let value = 0x10000 + (((high - 0xd800) as u32) << 10) + (low - 0xdc00) as u32;
+                    Ok(char::from_u32(value))
+                }
+                _ => decoder_error!(ParserErrorDetails::InvalidByteSequence(
+                    self.encoding(),
+                    start
+                )),
+            },
+            Some(unit) => match char::from_u32(unit as u32) {
+                Some(ch) => Ok(Some(ch)),
+                None => decoder_error!(ParserErrorDetails::InvalidByteSequence(
+                    self.encoding(),
+                    start
+                )),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+impl<B: BufRead> Decoder for Utf16Decoder<B> {
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode_next() {
+            Ok(ch) => ch,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.error.take()
+    }
+
+    fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        Some(self.encoding())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::utf16::Utf16Decoder;
+    use crate::decoders::{Decoder, Encoding};
+    use crate::errors::ParserErrorDetails;
+    use std::io::BufReader;
+
+    fn encode(s: &str, big_endian: bool) -> Vec<u8> {
+        s.encode_utf16()
+            .flat_map(|u| match big_endian {
+                true => u.to_be_bytes(),
+                false => u.to_le_bytes(),
+            })
+            .collect()
+    }
+
+    fn decode(bytes: &[u8], big_endian: bool) -> (String, Option<ParserErrorDetails>) {
+        let mut reader = BufReader::new(bytes);
+        let mut decoder = Utf16Decoder::new(&mut reader, big_endian);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
+        }
+        (decoded, decoder.take_error().map(|e| e.details))
+    }
+
+    #[test]
+    fn should_decode_both_byte_orders() {
+        let source = "{\"key\" : \"välue 😀\"}";
+        for big_endian in [false, true] {
+            assert_eq!(
+                decode(&encode(source, big_endian), big_endian),
+                (source.to_string(), None)
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_unpaired_surrogates_with_offsets() {
+        let mut bytes = encode("ab", false);
+        bytes.extend_from_slice(&0xd83du16.to_le_bytes());
+        bytes.extend_from_slice(&encode("c", false));
+        assert_eq!(
+            decode(&bytes, false),
+            (
+                "ab".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf16Le,
+                    4
+                ))
+            )
+        );
+        let bytes = [0x00, 0x61, 0xdc, 0x00];
+        assert_eq!(
+            decode(&bytes, true),
+            (
+                "a".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf16Be,
+                    2
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn should_report_truncated_input() {
+        let bytes = [0x61, 0x00, 0x62];
+        assert_eq!(
+            decode(&bytes, false),
+            (
+                "a".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf16Le,
+                    2
+                ))
+            )
+        );
+    }
+}
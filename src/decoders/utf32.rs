@@ -0,0 +1,165 @@
+//! A decoder implementation that will take an underlying byte source and produce a stream of
+//! `char`s decoded from either little or big endian UTF-32
+use crate::decoder_error;
+use crate::decoders::{ByteReader, Decoder, Encoding};
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::io::BufRead;
+
+/// A UTF-32 decoder, which reads from a [BufRead] instance.
+pub struct Utf32Decoder<B: BufRead> {
+    /// The underlying input
+    bytes: ByteReader<B>,
+    /// Whether code units are big endian
+    big_endian: bool,
+    /// Any error which has halted decoding
+    error: Option<ParserError>,
+}
+
+impl<B: BufRead> Utf32Decoder<B> {
+    /// Create a new decoder, specifying the byte order of the input
+    pub fn new(input: B, big_endian: bool) -> Self {
+        Utf32Decoder {
+            bytes: ByteReader::new(input),
+            big_endian,
+            error: None,
+        }
+    }
+
+    /// The [Encoding] being decoded
+    fn encoding(&self) -> Encoding {
+        if self.big_endian {
+            Encoding::Utf32Be
+        } else {
+            Encoding::Utf32Le
+        }
+    }
+
+    /// Attempt to decode the next character, rejecting surrogates and out of range values
+    fn decode_next(&mut self) -> ParserResult<Option<char>> {
+        let start = self.bytes.offset();
+        match self.bytes.next_bytes::<4>(self.encoding())? {
+            Some(bytes) => {
+                let value = match self.big_endian {
+                    true => u32::from_be_bytes(bytes),
+                    false => u32::from_le_bytes(bytes),
+                };
+                match char::from_u32(value) {
+                    Some(ch) => Ok(Some(ch)),
+                    None => decoder_error!(ParserErrorDetails::InvalidByteSequence(
+                        self.encoding(),
+                        start
+                    )),
+                }
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+impl<B: BufRead> Decoder for Utf32Decoder<B> {
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode_next() {
+            Ok(ch) => ch,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.error.take()
+    }
+
+    fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        Some(self.encoding())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::utf32::Utf32Decoder;
+    use crate::decoders::{Decoder, Encoding};
+    use crate::errors::ParserErrorDetails;
+    use std::io::BufReader;
+
+    fn encode(s: &str, big_endian: bool) -> Vec<u8> {
+        s.chars()
+            .flat_map(|c| match big_endian {
+                true => (c as u32).to_be_bytes(),
+                false => (c as u32).to_le_bytes(),
+            })
+            .collect()
+    }
+
+    fn decode(bytes: &[u8], big_endian: bool) -> (String, Option<ParserErrorDetails>) {
+        let mut reader = BufReader::new(bytes);
+        let mut decoder = Utf32Decoder::new(&mut reader, big_endian);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
+        }
+        (decoded, decoder.take_error().map(|e| e.details))
+    }
+
+    #[test]
+    fn should_decode_both_byte_orders() {
+        let source = "[\"välue 😀\", 1.5]";
+        for big_endian in [false, true] {
+            assert_eq!(
+                decode(&encode(source, big_endian), big_endian),
+                (source.to_string(), None)
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_code_points_with_offsets() {
+        let mut bytes = encode("ab", true);
+        bytes.extend_from_slice(&0x110000u32.to_be_bytes());
+        assert_eq!(
+            decode(&bytes, true),
+            (
+                "ab".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf32Be,
+                    8
+                ))
+            )
+        );
+        let mut bytes = encode("a", false);
+        bytes.extend_from_slice(&0xd800u32.to_le_bytes());
+        assert_eq!(
+            decode(&bytes, false),
+            (
+                "a".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf32Le,
+                    4
+                ))
+            )
+        );
+    }
+
+    #[test]
+    fn should_report_truncated_input() {
+        let bytes = [0x61, 0x00, 0x00, 0x00, 0x62, 0x00];
+        assert_eq!(
+            decode(&bytes, false),
+            (
+                "a".to_string(),
+                Some(ParserErrorDetails::InvalidByteSequence(
+                    Encoding::Utf32Le,
+                    4
+                ))
+            )
+        );
+    }
+}
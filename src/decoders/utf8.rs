@@ -0,0 +1,172 @@
+//! A decoder implementation that will take an underlying byte source and produce a stream of
+//! `char`s decoded from UTF-8, optionally replacing any invalid sequences with U+FFFD
+use crate::decoder_error;
+use crate::decoders::{ByteReader, Decoder, Encoding};
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::io::BufRead;
+
+/// A UTF-8 decoder, which reads from a [BufRead] instance. When lossy, each maximal invalid
+/// subsequence within the input is replaced with a single [char::REPLACEMENT_CHARACTER]. Otherwise
+/// an invalid sequence halts decoding
+pub struct Utf8Decoder<B: BufRead> {
+    /// The underlying input
+    bytes: ByteReader<B>,
+    /// Whether invalid sequences should be replaced rather than treated as an error
+    lossy: bool,
+    /// The number of replacements made so far
+    replacements: usize,
+    /// Any error which has halted decoding
+    error: Option<ParserError>,
+}
+
+impl<B: BufRead> Utf8Decoder<B> {
+    /// Create a new decoder, specifying whether invalid sequences should be replaced
+    pub fn new(input: B, lossy: bool) -> Self {
+        Utf8Decoder {
+            bytes: ByteReader::new(input),
+            lossy,
+            replacements: 0,
+            error: None,
+        }
+    }
+
+    /// Handle an invalid sequence starting at a given byte offset, either by replacing it or by
+    /// returning an error
+    #[inline]
+    fn invalid(&mut self, start: usize) -> ParserResult<Option<char>> {
+        if self.lossy {
+            self.replacements += 1;
+            Ok(Some(char::REPLACEMENT_CHARACTER))
+        } else {
+            decoder_error!(ParserErrorDetails::InvalidByteSequence(
+                Encoding::Utf8,
+                start
+            ))
+        }
+    }
+
+    /// Attempt to decode the next character. A continuation byte which doesn't fit the current
+    /// sequence is left in the input, so that it may start the next sequence
+    fn decode_next(&mut self) -> ParserResult<Option<char>> {
+        let start = self.bytes.offset();
+        let first = match self.bytes.next_byte()? {
+            Some(b) => b,
+            None => return Ok(None),
+        };
+        let (width, mut value) = match first {
+            0x00..=0x7f => return Ok(Some(first as char)),
+            0xc2..=0xdf => (2, (first & 0x1f) as u32),
+            0xe0..=0xef => (3, (first & 0x0f) as u32),
+            0xf0..=0xf4 => (4, (first & 0x07) as u32),
+            _ => return self.invalid(start),
+        };
+        for i in 1..width {
+            let range = match (i, first) {
+                (1, 0xe0) => 0xa0..=0xbf,
+                (1, 0xed) => 0x80..=0x9f,
+                (1, 0xf0) => 0x90..=0xbf,
+                (1, 0xf4) => 0x80..=0x8f,
+                _ => 0x80..=0xbf,
+            };
+            match self.bytes.peek_byte()? {
+                Some(b) if range.contains(&b) => {
+                    self.bytes.next_byte()?;
+                    value = (value << 6) | (b & 0x3f) as u32;
+                }
+                _ => return self.invalid(start),
+            }
+        }
+        Ok(char::from_u32(value))
+    }
+}
+
+impl<B: BufRead> Decoder for Utf8Decoder<B> {
+    fn next_char(&mut self) -> Option<char> {
+        if self.error.is_some() {
+            return None;
+        }
+        match self.decode_next() {
+            Ok(ch) => ch,
+            Err(err) => {
+                self.error = Some(err);
+                None
+            }
+        }
+    }
+
+    fn take_error(&mut self) -> Option<ParserError> {
+        self.error.take()
+    }
+
+    fn offset(&self) -> usize {
+        self.bytes.offset()
+    }
+
+    fn source_encoding(&self) -> Option<Encoding> {
+        if self.lossy {
+            Some(Encoding::Utf8Lossy)
+        } else {
+            Some(Encoding::Utf8)
+        }
+    }
+
+    fn replacements(&self) -> usize {
+        self.replacements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::decoders::utf8::Utf8Decoder;
+    use crate::decoders::{Decoder, Encoding};
+    use crate::errors::ParserErrorDetails;
+    use std::io::BufReader;
+
+    fn decode(bytes: &[u8]) -> (String, usize) {
+        let mut reader = BufReader::new(bytes);
+        let mut decoder = Utf8Decoder::new(&mut reader, true);
+        let mut decoded = String::new();
+        while let Some(ch) = decoder.next_char() {
+            decoded.push(ch);
+        }
+        (decoded, decoder.replacements())
+    }
+
+    #[test]
+    fn should_decode_valid_input_without_replacements() {
+        let source = "{\"naïve\" : [\"café\", \"€\", \"😀\"]}";
+        assert_eq!(decode(source.as_bytes()), (source.to_string(), 0));
+    }
+
+    #[test]
+    fn should_match_std_lossy_conversion() {
+        for bytes in [
+            &b"a\xffb"[..],
+            b"\xc3",
+            b"\xe2\x82x",
+            b"\xed\xa0\x80",
+            b"\xf0\x9f\x98",
+            b"\xf4\x90\x80\x80",
+            b"\xc0\xafz\x80\x80",
+            b"\xe0\x80\xaf",
+        ] {
+            let expected = String::from_utf8_lossy(bytes);
+            let replacements = expected.matches(char::REPLACEMENT_CHARACTER).count();
+            assert_eq!(decode(bytes), (expected.to_string(), replacements));
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_sequences_with_offsets_when_strict() {
+        let bytes = b"ab\xe2\x82x";
+        let mut reader = BufReader::new(&bytes[..]);
+        let mut decoder = Utf8Decoder::new(&mut reader, false);
+        assert_eq!(decoder.next_char(), Some('a'));
+        assert_eq!(decoder.next_char(), Some('b'));
+        assert_eq!(decoder.next_char(), None);
+        assert_eq!(
+            decoder.take_error().unwrap().details,
+            ParserErrorDetails::InvalidByteSequence(Encoding::Utf8, 2)
+        );
+    }
+}
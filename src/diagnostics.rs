@@ -0,0 +1,286 @@
+//! Rendering of [ParserError]s as human-readable diagnostics
+//!
+//! Given the original input, a [Renderer] will produce a diagnostic which includes the stable code
+//! associated with the error, the offending line(s) of the input with the location of the error
+//! underlined, the pointer to the value being parsed, and a hint as to how the error might be
+//! fixed:
+//!
+//! ```text
+//! error[E011]: invalid character: 'x'
+//!  --> line 3, column 9
+//!   |
+//! 2 |   "a" : 1,
+//! 3 |   "b" : x
+//!   |         ^
+//!   = pointer: /b
+//!   = hint: did you forget to enclose a string in double quotes?
+//! ```
+//!
+//! ```rust
+//! use chisel_json::diagnostics::Renderer;
+//! use chisel_json::dom::Parser;
+//!
+//! let input = "{\n  \"a\" : 1,\n  \"b\" : x\n}";
+//! let err = Parser::default().parse_str(input).err().unwrap();
+//! let rendered = Renderer::default().render(&err, input);
+//! assert!(rendered.starts_with("error[E011]"));
+//! ```
+use crate::coords::{Coords, Span};
+use crate::errors::ParserError;
+use std::fmt::Write;
+
+/// ANSI style used for the error heading
+const ERROR_STYLE: &str = "\x1b[1;31m";
+/// ANSI style used for the gutter and location
+const GUTTER_STYLE: &str = "\x1b[1;34m";
+/// ANSI style used for the hint
+const HINT_STYLE: &str = "\x1b[1;36m";
+/// ANSI sequence used to reset the style
+const RESET: &str = "\x1b[0m";
+
+/// Renders [ParserError]s against the input which produced them, either as plain text or using ANSI
+/// colour sequences for display within a terminal
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Renderer {
+    /// Whether to include ANSI colour sequences
+    colour: bool,
+    /// The number of lines of input to display before the offending line
+    context: usize,
+}
+
+impl Default for Renderer {
+    /// By default, plain text is rendered along with a single line of context
+    fn default() -> Self {
+        Renderer {
+            colour: false,
+            context: 1,
+        }
+    }
+}
+
+impl Renderer {
+    /// Enable or disable ANSI colour sequences within the rendered output
+    pub fn with_colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    /// Set the number of lines of input to display before the offending line
+    pub fn with_context(mut self, lines: usize) -> Self {
+        self.context = lines;
+        self
+    }
+
+    /// Render an error, marking the [Coords] held within the error (if any)
+    pub fn render(&self, error: &ParserError, input: &str) -> String {
+        self.render_range(error, error.coords.map(|coords| (coords, coords)), input)
+    }
+
+    /// Render an error, underlining a given [Span] of the input. A span which crosses lines is
+    /// underlined up to the end of its first line
+    pub fn render_span(&self, error: &ParserError, span: Span, input: &str) -> String {
+        self.render_range(error, Some((span.start, span.end)), input)
+    }
+
+    fn render_range(
+        &self,
+        error: &ParserError,
+        range: Option<(Coords, Coords)>,
+        input: &str,
+    ) -> String {
+        let mut out = String::new();
+        let heading = format!("error[{}]", error.details.code());
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            self.paint(ERROR_STYLE, &heading),
+            error.details
+        );
+
+        let lines = split_lines(input);
+        let mut width = 1;
+        if let Some((start, end)) = range {
+            let (line, column) = locate(input, start.absolute);
+            let (end_line, end_column) = locate(input, end.absolute);
+            width = line.to_string().len();
+            let _ = writeln!(
+                out,
+                "{:width$}{} line {}, column {}",
+                "",
+                self.paint(GUTTER_STYLE, "-->"),
+                line,
+                column,
+            );
+            let _ = writeln!(out, "{:width$} {}", "", self.paint(GUTTER_STYLE, "|"));
+            for number in line.saturating_sub(self.context).max(1)..=line {
+                let text = lines.get(number - 1).copied().unwrap_or_default();
+                let gutter = format!("{:>width$} |", number);
+                let _ = writeln!(out, "{} {}", self.paint(GUTTER_STYLE, &gutter), text);
+            }
+            let text = lines.get(line - 1).copied().unwrap_or_default();
+            let padding: String = text
+                .chars()
+                .take(column - 1)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let length = if end_line == line && end_column > column {
+                end_column - column + 1
+            } else {
+                1
+            };
+            let _ = writeln!(
+                out,
+                "{:width$} {} {}{}",
+                "",
+                self.paint(GUTTER_STYLE, "|"),
+                padding,
+                self.paint(ERROR_STYLE, &"^".repeat(length))
+            );
+        }
+
+        if let Some(pointer) = &error.pointer {
+            let _ = writeln!(
+                out,
+                "{:width$} {} {}",
+                "",
+                self.paint(HINT_STYLE, "= pointer:"),
+                pointer
+            );
+        }
+        if let Some(hint) = error.details.hint() {
+            let _ = writeln!(
+                out,
+                "{:width$} {} {}",
+                "",
+                self.paint(HINT_STYLE, "= hint:"),
+                hint
+            );
+        }
+        out
+    }
+
+    /// Wrap some text within a given ANSI style, if colour is enabled
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.colour {
+            format!("{}{}{}", style, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Convert an absolute (1-based) character position into a 1-based line and column within the
+/// input, treating `\n`, `\r\n` and a lone `\r` as line breaks in the same way as the lexer.
+/// Positions beyond the end of the input are clamped to the final character
+fn locate(input: &str, absolute: usize) -> (usize, usize) {
+    let (mut line, mut column) = (1, 1);
+    let mut chars = input.chars().peekable();
+    let mut index = 0;
+    while let Some(ch) = chars.next() {
+        index += 1;
+        if index >= absolute {
+            break;
+        }
+        let line_break = match ch {
+            '\n' => true,
+            '\r' => chars.peek() != Some(&'\n'),
+            _ => false,
+        };
+        if line_break {
+            line += 1;
+            column = 1;
+        } else {
+            column += 1;
+        }
+    }
+    (line, column)
+}
+
+/// Split the input into lines, treating `\n`, `\r\n` and a lone `\r` as line breaks
+fn split_lines(input: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    let mut chars = input.char_indices().peekable();
+    while let Some((index, ch)) = chars.next() {
+        match ch {
+            '\n' => lines.push(&input[start..index]),
+            '\r' if chars.peek().map(|(_, c)| *c) == Some('\n') => {
+                lines.push(&input[start..index]);
+                chars.next();
+            }
+            '\r' => lines.push(&input[start..index]),
+            _ => continue,
+        }
+        start = chars.peek().map_or(input.len(), |(next, _)| *next);
+    }
+    if start < input.len() {
+        lines.push(&input[start..]);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords::Span;
+    use crate::diagnostics::{locate, split_lines, Renderer};
+    use crate::dom::Parser;
+
+    #[test]
+    fn should_locate_absolute_positions() {
+        let input = "ab\ncd\n\nef";
+        assert_eq!(locate(input, 1), (1, 1));
+        assert_eq!(locate(input, 2), (1, 2));
+        assert_eq!(locate(input, 4), (2, 1));
+        assert_eq!(locate(input, 7), (3, 1));
+        assert_eq!(locate(input, 9), (4, 2));
+        assert_eq!(locate(input, 100), (4, 3));
+    }
+
+    #[test]
+    fn should_treat_crlf_and_lone_cr_as_single_line_breaks() {
+        let input = "ab\r\ncd\ref";
+        assert_eq!(locate(input, 4), (1, 4));
+        assert_eq!(locate(input, 5), (2, 1));
+        assert_eq!(locate(input, 8), (3, 1));
+        assert_eq!(split_lines(input), ["ab", "cd", "ef"]);
+        assert_eq!(split_lines("ab\n\ncd\n"), ["ab", "", "cd"]);
+    }
+
+    #[test]
+    fn should_render_plain_text_with_a_caret_and_hint() {
+        let input = "{\n  \"a\" : 1,\n  \"b\" : x\n}";
+        let err = Parser::default().parse_str(input).err().unwrap();
+        assert_eq!(
+            Renderer::default().render(&err, input),
+            "error[E011]: invalid character: 'x'\n \
+             --> line 3, column 9\n  \
+             |\n\
+             2 |   \"a\" : 1,\n\
+             3 |   \"b\" : x\n  \
+             |         ^\n  \
+             = pointer: /b\n  \
+             = hint: did you forget to enclose a string in double quotes?\n"
+        );
+    }
+
+    #[test]
+    fn should_underline_spans_and_apply_colour() {
+        let input = "[1, 2, nul]";
+        let err = Parser::default().parse_str(input).err().unwrap();
+        let start = err.coords.unwrap();
+        let mut end = start;
+        end.inc_n(2);
+        let span = Span {
+            start,
+            end,
+            end_offset: end.offset + 1,
+        };
+        let rendered = Renderer::default()
+            .with_context(0)
+            .render_span(&err, span, input);
+        assert!(rendered.contains("1 | [1, 2, nul]\n  |        ^^^\n"));
+        let coloured = Renderer::default().with_colour(true).render(&err, input);
+        assert!(coloured.starts_with("\x1b[1;31merror[E012]\x1b[0m"));
+        assert!(coloured.contains("\x1b[1;36m= hint:\x1b[0m"));
+    }
+}
@@ -15,13 +15,17 @@ use std::rc::Rc;
 use crate::coords::Span;
 use crate::dom_parser_error;
 use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
-use crate::lexer::{Lexer, Token};
+use crate::lexer::{Lexer, PackedToken, Token};
+use crate::options::{KeyOutcome, KeyTracker, LimitTracker, ParserOptions, Recovery, Separators};
+use crate::report::{Outcome, ParseReport};
+use crate::spans::SpanRecorder;
+use crate::streams::{Lines, Record};
 use crate::JsonValue;
 
 /// Main JSON parser struct
 pub struct Parser {
     decoders: DecoderSelector,
-    encoding: Encoding,
+    options: ParserOptions,
 }
 
 impl Default for Parser {
@@ -29,7 +33,7 @@ impl Default for Parser {
     fn default() -> Self {
         Self {
             decoders: Default::default(),
-            encoding: Default::default(),
+            options: Default::default(),
         }
     }
 }
@@ -39,113 +43,653 @@ impl Parser {
     pub fn with_encoding(encoding: Encoding) -> Self {
         Self {
             decoders: Default::default(),
-            encoding,
+            options: ParserOptions::default().with_encoding(encoding),
         }
     }
 
-    ///
-    pub fn parse_file<PathLike: AsRef<Path>>(&self, path: PathLike) -> ParserResult<JsonValue> {
+    /// Create a new instance of the parser configured using a given set of [ParserOptions]
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            decoders: Default::default(),
+            options,
+        }
+    }
+
+    /// Get the [ParserOptions] currently in use by the parser
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Parse the contents of a file located at a given path, decoded using the configured
+    /// [Encoding]
+    pub fn parse_file<PathLike: AsRef<Path>>(&self, path: PathLike) -> ParserResult<JsonValue<'_>> {
+        self.parse_file_with_report(path).result
+    }
+
+    /// Parse the contents of a file located at a given path, returning a [ParseReport] alongside
+    /// the result
+    pub fn parse_file_with_report<PathLike: AsRef<Path>>(
+        &self,
+        path: PathLike,
+    ) -> Outcome<JsonValue<'_>> {
         match File::open(&path) {
             Ok(f) => {
                 let mut reader = BufReader::new(f);
-                let mut chars = self.decoders.new_decoder(&mut reader, self.encoding);
-                self.parse(&mut chars)
-            }
-            Err(_) => {
-                dom_parser_error!(ParserErrorDetails::InvalidFile)
+                let chars = self
+                    .decoders
+                    .new_decoder(&mut reader, self.options.encoding);
+                self.parse_lexer(Lexer::with_decoder(chars, &self.options), false)
             }
+            Err(err) => Outcome {
+                result: dom_parser_error!(ParserErrorDetails::InvalidFile, cause = err),
+                report: ParseReport::default(),
+            },
         }
     }
 
-    pub fn parse_bytes(&self, bytes: &[u8]) -> ParserResult<JsonValue> {
+    /// Parse a slice of bytes, decoded using the configured [Encoding]
+    pub fn parse_bytes(&self, bytes: &[u8]) -> ParserResult<JsonValue<'_>> {
+        self.parse_bytes_with_report(bytes).result
+    }
+
+    /// Parse a slice of bytes, returning a [ParseReport] alongside the result
+    pub fn parse_bytes_with_report(&self, bytes: &[u8]) -> Outcome<JsonValue<'_>> {
         let mut reader = BufReader::new(bytes);
-        let mut chars = self.decoders.default_decoder(&mut reader);
-        self.parse(&mut chars)
+        let chars = self
+            .decoders
+            .new_decoder(&mut reader, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), false)
     }
 
-    pub fn parse_str(&self, str: &str) -> ParserResult<JsonValue> {
-        let mut reader = BufReader::new(str.as_bytes());
-        let mut chars = self.decoders.default_decoder(&mut reader);
-        self.parse(&mut chars)
+    /// Parse the bytes underlying a string slice, decoded using the configured [Encoding]
+    pub fn parse_str(&self, str: &str) -> ParserResult<JsonValue<'_>> {
+        self.parse_str_with_report(str).result
     }
 
-    /// Parse the contents of a buffer (e.g. implementation of [BufRead])
-    pub fn parse_buffer<Callback>(&self, buffer: &mut impl BufRead) -> ParserResult<JsonValue> {
-        let mut chars = self.decoders.default_decoder(buffer);
-        self.parse(&mut chars)
+    /// Parse the bytes underlying a string slice, returning a [ParseReport] alongside the result
+    pub fn parse_str_with_report(&self, str: &str) -> Outcome<JsonValue<'_>> {
+        self.parse_bytes_with_report(str.as_bytes())
     }
 
-    pub fn parse(&self, chars: &mut impl Iterator<Item = char>) -> ParserResult<JsonValue> {
-        let mut lexer = Lexer::new(chars);
-        match lexer.consume()? {
-            (Token::StartObject, _) => self.parse_object(&mut lexer),
-            (Token::StartArray, _) => self.parse_array(&mut lexer),
+    /// Parse the contents of a buffer (e.g. implementation of [BufRead]), decoded using the
+    /// configured [Encoding]
+    pub fn parse_buffer(&self, buffer: &mut impl BufRead) -> ParserResult<JsonValue<'_>> {
+        self.parse_buffer_with_report(buffer).result
+    }
+
+    /// Parse the contents of a buffer, returning a [ParseReport] alongside the result
+    pub fn parse_buffer_with_report(&self, buffer: &mut impl BufRead) -> Outcome<JsonValue<'_>> {
+        let chars = self.decoders.new_decoder(buffer, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), false)
+    }
+
+    /// Parse a stream of `char`s into a [JsonValue]
+    pub fn parse(&self, chars: &mut impl Iterator<Item = char>) -> ParserResult<JsonValue<'_>> {
+        self.parse_with_report(chars).result
+    }
+
+    /// Parse a stream of `char`s into a [JsonValue], returning a [ParseReport] alongside the
+    /// result
+    pub fn parse_with_report(
+        &self,
+        chars: &mut impl Iterator<Item = char>,
+    ) -> Outcome<JsonValue<'_>> {
+        self.parse_lexer(Lexer::with_options(chars, &self.options), false)
+    }
+
+    /// Parse a stream of newline-delimited JSON records (see [crate::streams]), read from a given
+    /// [BufRead]. Records are parsed lazily, as the returned iterator is advanced. Unless
+    /// [ParserOptions::with_skip_invalid_records] is in use, the iterator finishes after returning
+    /// the first error found
+    pub fn parse_lines<R: BufRead>(&self, reader: R) -> Records<'_, R> {
+        Records {
+            parser: self,
+            lines: Lines::new(reader, ParserErrorSource::DomParser, self.options.encoding),
+            skipped: vec![],
+            done: false,
+        }
+    }
+
+    /// Parse a stream of back-to-back values (see [crate::streams]), read from a given [BufRead]
+    /// and decoded using the configured [Encoding]. The values may simply be concatenated, or may
+    /// form an RFC 7464 JSON text sequence in which each record is preceded by a record separator.
+    /// Values are parsed lazily, as the returned iterator is advanced
+    pub fn parse_sequence<'a, B: BufRead>(&'a self, reader: &'a mut B) -> Sequence<'a> {
+        let chars = self.decoders.new_decoder(reader, self.options.encoding);
+        Sequence {
+            parser: self,
+            lexer: Lexer::with_decoder(chars, &self.options).with_record_separators(),
+            count: 0,
+            separated: false,
+            done: false,
+        }
+    }
+
+    /// Parse a single record from a stream of newline-delimited JSON. Unlike a whole document, a
+    /// record may hold any value at its root
+    fn parse_record(&self, bytes: &[u8]) -> Outcome<JsonValue<'_>> {
+        let mut reader = BufReader::new(bytes);
+        let chars = self
+            .decoders
+            .new_decoder(&mut reader, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), true)
+    }
+
+    /// Parse the [Token]s produced by a given [Lexer] into a [JsonValue], reporting any
+    /// replacements made whilst decoding the input along with any errors found. Scalar values are
+    /// only accepted at the root when `scalars` is set
+    fn parse_lexer(&self, mut lexer: Lexer, scalars: bool) -> Outcome<JsonValue<'_>> {
+        let mut report = ParseReport::default();
+        let token = lexer.consume();
+        let result = self.parse_document(&mut lexer, token, scalars, &mut report);
+        report.encoding = lexer.encoding();
+        report.replacements = lexer.replacements();
+        Outcome { result, report }
+    }
+
+    /// Parse a single top-level value, given the result of consuming its first [Token], reporting
+    /// any errors found and (optionally) the spans of each value
+    fn parse_document(
+        &self,
+        lexer: &mut Lexer,
+        token: ParserResult<PackedToken>,
+        scalars: bool,
+        report: &mut ParseReport,
+    ) -> ParserResult<JsonValue<'_>> {
+        let mut recovery = Recovery::new(self.options.recover);
+        let mut spans = SpanRecorder::new(&self.options);
+        let result = token
+            .and_then(|token| self.parse_root(lexer, token, scalars, &mut recovery, &mut spans));
+        report.errors = recovery.finish(&result);
+        report.spans = spans.finish();
+        result
+    }
+
+    fn parse_root(
+        &self,
+        lexer: &mut Lexer,
+        token: PackedToken,
+        scalars: bool,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+    ) -> ParserResult<JsonValue<'_>> {
+        let mut limits = LimitTracker::new(self.options.limits);
+        self.check_limit(lexer, limits.node())?;
+        match token {
+            (Token::StartObject, span) => {
+                self.parse_object(lexer, &mut limits, recovery, spans, span)
+            }
+            (Token::StartArray, span) => {
+                self.parse_array(lexer, &mut limits, recovery, spans, span)
+            }
+            (token, span) if scalars && token != Token::EndOfInput => {
+                self.parse_scalar(token, span, spans)
+            }
             (_, span) => {
                 dom_parser_error!(ParserErrorDetails::InvalidRootObject, span.start)
             }
         }
     }
 
-    fn parse_value(&self, lexer: &mut Lexer) -> ParserResult<JsonValue> {
-        match lexer.consume()? {
-            (Token::StartObject, _) => self.parse_object(lexer),
-            (Token::StartArray, _) => self.parse_array(lexer),
-            (Token::Str(str), _) => Ok(JsonValue::String(Cow::Owned(str))),
-            (Token::Float(value), _) => Ok(JsonValue::Float(value)),
-            (Token::Integer(value), _) => Ok(JsonValue::Integer(value)),
-            (Token::Boolean(value), _) => Ok(JsonValue::Boolean(value)),
-            (Token::Null, _) => Ok(JsonValue::Null),
-            (token, span) => {
-                dom_parser_error!(ParserErrorDetails::UnexpectedToken(token), span.start)
+    fn parse_value(
+        &self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+    ) -> ParserResult<JsonValue<'_>> {
+        let (token, span) = lexer.consume()?;
+        self.check_limit(lexer, limits.node())?;
+        match token {
+            Token::StartObject => self.parse_object(lexer, limits, recovery, spans, span),
+            Token::StartArray => self.parse_array(lexer, limits, recovery, spans, span),
+            token => self.parse_scalar(token, span, spans),
+        }
+    }
+
+    /// Convert a [Token] holding a scalar value into a [JsonValue], recording its [Span]
+    fn parse_scalar(
+        &self,
+        token: Token,
+        span: Span,
+        spans: &mut SpanRecorder,
+    ) -> ParserResult<JsonValue<'_>> {
+        let value = match token {
+            Token::Str(str) => JsonValue::String(Cow::Owned(str)),
+            Token::Float(value) => JsonValue::Float(value),
+            Token::Integer(value) => JsonValue::Integer(value),
+            Token::RawNumber(value) => JsonValue::RawNumber(Cow::Owned(value)),
+            Token::Boolean(value) => JsonValue::Boolean(value),
+            Token::Null => JsonValue::Null,
+            token => {
+                return dom_parser_error!(ParserErrorDetails::UnexpectedToken(token), span.start)
+            }
+        };
+        spans.record(span);
+        Ok(value)
+    }
+
+    /// An object is just a list of comma separated KV pairs. The [Span] of the opening brace is
+    /// used to record the span of the whole object
+    fn parse_object(
+        &self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+        start: Span,
+    ) -> ParserResult<JsonValue<'_>> {
+        self.check_limit(lexer, limits.enter())?;
+        let mut object = ObjectState {
+            pairs: vec![],
+            keys: KeyTracker::new(self.options.duplicate_keys),
+            separators: Separators::new(&self.options, ParserErrorSource::DomParser),
+            count: 0,
+        };
+        let end = loop {
+            match self.parse_object_step(lexer, limits, recovery, spans, &mut object) {
+                Ok(Some(end)) => break end,
+                Ok(None) => (),
+                Err(err) => match recovery.recover(lexer, err)? {
+                    (Token::Comma, comma) => object.separators.resume(comma),
+                    (_, end) => break end,
+                },
+            }
+        };
+        limits.exit();
+        spans.record(Span {
+            start: start.start,
+            end: end.end,
+            end_offset: end.end_offset,
+        });
+        Ok(JsonValue::Object(object.pairs))
+    }
+
+    /// Parse the next member of an object, returning the [Span] of the closing brace once the end
+    /// of the object is reached
+    fn parse_object_step<'p>(
+        &'p self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+        object: &mut ObjectState<'p>,
+    ) -> ParserResult<Option<Span>> {
+        let (token, span) = lexer.consume_key()?;
+        if let Token::Str(_) | Token::Identifier(_) = token {
+            object.separators.member(&token, span)?;
+        }
+        match token {
+            Token::Str(str) | Token::Identifier(str) => {
+                object.count += 1;
+                self.check_limit(lexer, limits.members(object.count))?;
+                let mut outcome = object.keys.observe(&str, span.start);
+                if let KeyOutcome::Reject(first) = outcome {
+                    recovery.tolerate(dom_parser_error!(
+                        ParserErrorDetails::DuplicateKey(
+                            str.clone(),
+                            Box::new((first, span.start))
+                        ),
+                        span.start
+                    ))?;
+                    outcome = KeyOutcome::Discard;
+                }
+                let mark = recovery.mark();
+                let recording = spans.suspend(outcome == KeyOutcome::Discard);
+                spans.push_name(&str, span);
+                let result = self.parse_member_value(lexer, limits, recovery, spans);
+                spans.pop();
+                spans.resume(recording);
+                let (value, failure) = match result {
+                    Ok(value) => (value, None),
+                    Err(err) => (JsonValue::Invalid, Some(err.within_name(&str))),
+                };
+                recovery.within_name(mark, &str);
+                match outcome {
+                    KeyOutcome::Replace(index) => object.pairs[index].1 = value,
+                    KeyOutcome::Discard => (),
+                    _ => object.pairs.push((str, value)),
+                }
+                match failure {
+                    Some(err) => Err(err),
+                    None => Ok(None),
+                }
+            }
+            Token::Comma => {
+                object.separators.comma(span)?;
+                Ok(None)
             }
+            Token::EndObject => {
+                object.separators.end()?;
+                Ok(Some(span))
+            }
+            Token::EndOfInput => dom_parser_error!(ParserErrorDetails::EndOfInput, span.start),
+            _ => dom_parser_error!(ParserErrorDetails::InvalidObject, span.start),
         }
     }
 
-    /// An object is just a list of comma separated KV pairs
-    fn parse_object(&self, lexer: &mut Lexer) -> ParserResult<JsonValue> {
-        let mut pairs = vec![];
-        loop {
-            match lexer.consume()? {
-                (Token::Str(str), _) => {
-                    let should_be_colon = lexer.consume()?;
-                    match should_be_colon {
-                        (Token::Colon, _) => pairs.push((str, self.parse_value(lexer)?)),
-                        (_, _) => {
-                            return dom_parser_error!(
-                                ParserErrorDetails::PairExpected,
-                                should_be_colon.1.start
-                            )
+    /// Parse the colon separator and value associated with a key within an object
+    fn parse_member_value(
+        &self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+    ) -> ParserResult<JsonValue<'_>> {
+        match lexer.consume()? {
+            (Token::Colon, _) => self.parse_value(lexer, limits, recovery, spans),
+            (_, span) => dom_parser_error!(ParserErrorDetails::PairExpected, span.start),
+        }
+    }
+
+    /// Convert the outcome of a [LimitTracker] check into a parser error
+    #[inline]
+    fn check_limit(
+        &self,
+        lexer: &Lexer,
+        check: Result<(), ParserErrorDetails>,
+    ) -> ParserResult<()> {
+        match check {
+            Ok(()) => Ok(()),
+            Err(details) => dom_parser_error!(details, lexer.coords()),
+        }
+    }
+
+    /// An array is just a list of comma separated values. Any error is attributed to the element
+    /// being parsed at the time. The [Span] of the opening bracket is used to record the span of
+    /// the whole array
+    fn parse_array(
+        &self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+        start: Span,
+    ) -> ParserResult<JsonValue<'_>> {
+        let mut index = 0;
+        self.parse_elements(lexer, limits, recovery, spans, start, &mut index)
+            .map_err(|err| err.within_index(index))
+    }
+
+    fn parse_elements(
+        &self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+        start: Span,
+        index: &mut usize,
+    ) -> ParserResult<JsonValue<'_>> {
+        self.check_limit(lexer, limits.enter())?;
+        let mut array = ArrayState {
+            values: vec![],
+            separators: Separators::new(&self.options, ParserErrorSource::DomParser),
+        };
+        let end = loop {
+            match self.parse_array_step(lexer, limits, recovery, spans, &mut array, index) {
+                Ok(Some(end)) => break end,
+                Ok(None) => (),
+                Err(err) => {
+                    if err.details != ParserErrorDetails::InvalidArray {
+                        array.values.push(JsonValue::Invalid);
+                    }
+                    let mark = recovery.mark();
+                    let resumed = recovery.recover(lexer, err)?;
+                    recovery.within_index(mark, *index);
+                    match resumed {
+                        (Token::Comma, comma) => {
+                            array.separators.resume(comma);
+                            *index += 1;
                         }
+                        (_, end) => break end,
                     }
                 }
-                (Token::Comma, _) => (),
-                (Token::EndObject, _) => return Ok(JsonValue::Object(pairs)),
-                (_token, span) => {
-                    return dom_parser_error!(ParserErrorDetails::InvalidObject, span.start);
+            }
+        };
+        limits.exit();
+        spans.record(Span {
+            start: start.start,
+            end: end.end,
+            end_offset: end.end_offset,
+        });
+        Ok(JsonValue::Array(array.values))
+    }
+
+    /// Parse the next element of an array, returning the [Span] of the closing bracket once the
+    /// end of the array is reached
+    fn parse_array_step<'p>(
+        &'p self,
+        lexer: &mut Lexer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        spans: &mut SpanRecorder,
+        array: &mut ArrayState<'p>,
+        index: &mut usize,
+    ) -> ParserResult<Option<Span>> {
+        let (token, span) = lexer.consume()?;
+        match token {
+            Token::EndOfInput => {
+                return dom_parser_error!(ParserErrorDetails::EndOfInput, span.start)
+            }
+            Token::EndArray => array.separators.end()?,
+            Token::Comma => array.separators.comma(span)?,
+            _ => {
+                array.separators.member(&token, span)?;
+                self.check_limit(lexer, limits.members(array.values.len() + 1))?;
+                self.check_limit(lexer, limits.node())?;
+            }
+        }
+        let value = match token {
+            Token::StartArray | Token::StartObject => {
+                let mark = recovery.mark();
+                spans.push_index(*index);
+                let value = match token {
+                    Token::StartArray => self.parse_array(lexer, limits, recovery, spans, span),
+                    _ => self.parse_object(lexer, limits, recovery, spans, span),
+                };
+                spans.pop();
+                recovery.within_index(mark, *index);
+                array.values.push(value?);
+                return Ok(None);
+            }
+            Token::EndArray => return Ok(Some(span)),
+            Token::Comma => {
+                *index += 1;
+                return Ok(None);
+            }
+            Token::Str(str) => JsonValue::String(Cow::Owned(str)),
+            Token::Float(value) => JsonValue::Float(value),
+            Token::Integer(value) => JsonValue::Integer(value),
+            Token::RawNumber(value) => JsonValue::RawNumber(Cow::Owned(value)),
+            Token::Boolean(value) => JsonValue::Boolean(value),
+            Token::Null => JsonValue::Null,
+            _ => return dom_parser_error!(ParserErrorDetails::InvalidArray, span.start),
+        };
+        spans.push_index(*index);
+        spans.record(span);
+        spans.pop();
+        array.values.push(value);
+        Ok(None)
+    }
+}
+
+/// The state associated with an array whilst its elements are parsed
+struct ArrayState<'a> {
+    /// The elements parsed so far
+    values: Vec<JsonValue<'a>>,
+    /// The commas seen so far
+    separators: Separators,
+}
+
+/// The state associated with an object whilst its members are parsed
+struct ObjectState<'a> {
+    /// The members parsed so far
+    pairs: Vec<(String, JsonValue<'a>)>,
+    /// The keys seen so far
+    keys: KeyTracker,
+    /// The commas seen so far
+    separators: Separators,
+    /// The number of members seen so far
+    count: usize,
+}
+
+/// An iterator over the [Record]s within a stream of newline-delimited JSON, created by
+/// [Parser::parse_lines]
+pub struct Records<'p, R> {
+    /// The parser used for each record
+    parser: &'p Parser,
+    /// The lines of the stream
+    lines: Lines<R>,
+    /// The errors raised by any records which have been skipped
+    skipped: Vec<ParserError>,
+    /// Whether the end of the stream (or an error which stops the parse) has been reached
+    done: bool,
+}
+
+impl<'p, R> Records<'p, R> {
+    /// Get the errors raised by each record skipped so far, when
+    /// [ParserOptions::with_skip_invalid_records] is in use
+    pub fn skipped(&self) -> &[ParserError] {
+        &self.skipped
+    }
+}
+
+impl<'p, R: BufRead> Iterator for Records<'p, R> {
+    type Item = ParserResult<Record<JsonValue<'p>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.lines.advance() {
+                Ok(true) => match self.parser.parse_record(self.lines.line()) {
+                    Outcome {
+                        result: Ok(value),
+                        report,
+                    } => return Some(Ok(self.lines.record(value, report.errors))),
+                    Outcome {
+                        result: Err(err), ..
+                    } if self.parser.options.skip_invalid_records => {
+                        self.skipped.push(self.lines.locate(err))
+                    }
+                    Outcome {
+                        result: Err(err), ..
+                    } => {
+                        self.done = true;
+                        return Some(Err(self.lines.locate(err)));
+                    }
+                },
+                Ok(false) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
                 }
             }
         }
+        None
     }
+}
 
-    /// An array is just a list of comma separated values
-    fn parse_array(&self, lexer: &mut Lexer) -> ParserResult<JsonValue> {
-        let mut values: Vec<JsonValue> = vec![];
+/// An iterator over the values within a stream of concatenated JSON or an RFC 7464 JSON text
+/// sequence, created by [Parser::parse_sequence]
+pub struct Sequence<'a> {
+    /// The parser used for each value
+    parser: &'a Parser,
+    /// The lexer, which is shared by every value within the stream
+    lexer: Lexer<'a>,
+    /// The number of values started so far
+    count: usize,
+    /// Whether a record separator has been found, so that the stream is a JSON text sequence
+    separated: bool,
+    /// Whether the end of the stream (or an error which stops the parse) has been reached
+    done: bool,
+}
+
+impl<'a> Sequence<'a> {
+    /// Handle an error raised part way through a value. A record cut short by a record separator
+    /// or by the end of the input is reported as truncated. Within a JSON text sequence, parsing
+    /// then carries on from the next record separator, whereas any other error within a stream of
+    /// concatenated values stops the parse
+    fn fail(&mut self, err: ParserError) -> ParserError {
+        let truncated = match err.details {
+            ParserErrorDetails::TruncatedRecord
+            | ParserErrorDetails::UnexpectedToken(Token::RecordSeparator) => {
+                self.separated = true;
+                true
+            }
+            _ if err.details == ParserErrorDetails::EndOfInput || self.lexer.at_end() => {
+                self.done = true;
+                true
+            }
+            _ if self.separated => {
+                self.skip_record();
+                false
+            }
+            _ => {
+                self.done = true;
+                false
+            }
+        };
+        match (truncated, err.coords) {
+            (true, Some(coords)) => ParserError {
+                source: ParserErrorSource::DomParser,
+                details: ParserErrorDetails::TruncatedRecord,
+                coords: Some(coords),
+                cause: None,
+                pointer: err.pointer,
+            },
+            _ => err,
+        }
+    }
+
+    /// Skip forward to the next record separator, which is left to be consumed as the next token
+    fn skip_record(&mut self) {
         loop {
-            match lexer.consume()? {
-                (Token::StartArray, _) => values.push(self.parse_array(lexer)?),
-                (Token::EndArray, _) => return Ok(JsonValue::Array(values)),
-                (Token::StartObject, _) => values.push(self.parse_object(lexer)?),
-                (Token::Str(str), _) => values.push(JsonValue::String(Cow::Owned(str))),
-                (Token::Float(value), _) => values.push(JsonValue::Float(value)),
-                (Token::Integer(value), _) => values.push(JsonValue::Integer(value)),
-                (Token::Boolean(value), _) => values.push(JsonValue::Boolean(value)),
-                (Token::Null, _) => values.push(JsonValue::Null),
-                (Token::Comma, _) => (),
-                (_token, span) => {
-                    return dom_parser_error!(ParserErrorDetails::InvalidArray, span.start);
-                }
+            match self.lexer.consume() {
+                Ok((Token::RecordSeparator, _)) => return,
+                Ok((Token::EndOfInput, _)) => break,
+                Err(err) if err.details == ParserErrorDetails::TruncatedRecord => return,
+                Err(err) if !err.is_recoverable() => break,
+                _ => (),
             }
         }
+        self.done = true;
+    }
+}
+
+impl<'a> Iterator for Sequence<'a> {
+    type Item = ParserResult<Record<JsonValue<'a>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let token = match self.lexer.consume() {
+                Ok((Token::EndOfInput, _)) => break,
+                Ok((Token::RecordSeparator, _)) => {
+                    self.separated = true;
+                    continue;
+                }
+                token => token,
+            };
+            let index = self.count;
+            self.count += 1;
+            let line = match &token {
+                Ok((_, span)) => span.start.line,
+                Err(_) => self.lexer.coords().line,
+            };
+            let mut report = ParseReport::default();
+            return match self
+                .parser
+                .parse_document(&mut self.lexer, token, false, &mut report)
+            {
+                Ok(value) => Some(Ok(Record {
+                    index,
+                    line,
+                    value,
+                    errors: report.errors,
+                })),
+                Err(err) => Some(Err(self.fail(err))),
+            };
+        }
+        self.done = true;
+        None
     }
 }
 
@@ -153,14 +697,22 @@ impl Parser {
 mod tests {
     #![allow(unused_macros)]
 
-    use crate::decoders::DecoderSelector;
+    use crate::decoders::{DecoderSelector, Encoding};
     use crate::dom::Parser;
-    use crate::errors::ParserErrorDetails;
+    use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource};
+    use crate::options::{
+        CommentPolicy, Dialect, DuplicateKeyPolicy, Extension, Limits, NumericMode, ParserOptions,
+        ParserWarning, DEFAULT_MAX_DEPTH,
+    };
+    use crate::pointer::JsonPointer;
     use crate::relative_file;
+    use crate::JsonValue;
     use bytesize::ByteSize;
+    use std::error::Error;
     use std::fs::File;
-    use std::io::BufReader;
+    use std::io::{self, BufReader, Read};
     use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
     use std::time::Instant;
     use std::{env, fs};
 
@@ -229,4 +781,845 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn should_apply_duplicate_key_policies() {
+        let source = r#"{"a" : 1, "b" : 2, "a" : 3}"#;
+        for (policy, expected) in [
+            (
+                DuplicateKeyPolicy::KeepAll,
+                vec![("a", 1), ("b", 2), ("a", 3)],
+            ),
+            (DuplicateKeyPolicy::FirstWins, vec![("a", 1), ("b", 2)]),
+            (DuplicateKeyPolicy::LastWins, vec![("a", 3), ("b", 2)]),
+        ] {
+            let parser =
+                Parser::with_options(ParserOptions::default().with_duplicate_key_policy(policy));
+            match parser.parse_str(source).unwrap() {
+                JsonValue::Object(pairs) => {
+                    let pairs: Vec<(String, i64)> = pairs
+                        .into_iter()
+                        .map(|(k, v)| match v {
+                            JsonValue::Integer(i) => (k.replace('\"', ""), i),
+                            JsonValue::Float(f) => (k.replace('\"', ""), f as i64),
+                            _ => panic!(),
+                        })
+                        .collect();
+                    let expected: Vec<(String, i64)> =
+                        expected.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+                    assert_eq!(pairs, expected);
+                }
+                _ => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn should_reject_duplicate_keys_when_required() {
+        let parser = Parser::with_options(
+            ParserOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Error),
+        );
+        let parsed = parser.parse_str("{\"a\" : 1, \"a\" : 2}");
+        assert!(parsed.is_err());
+        let err = parsed.err().unwrap();
+        assert_eq!(err.coords.unwrap().column, 11);
+        match err.details {
+            ParserErrorDetails::DuplicateKey(key, occurrences) => {
+                assert_eq!(key, "\"a\"");
+                assert_eq!((occurrences.0.column, occurrences.1.column), (2, 11));
+            }
+            _ => panic!(),
+        }
+        for input in [
+            "{\"a\" : 1, \"\\u0061\" : 2}",
+            "{\"\\ud83d\\ude00\" : 1, \"😀\" : 2}",
+            "{\"a/b\" : 1, \"a\\/b\" : 2}",
+            "{\"\\n\" : 1, \"\\u000a\" : 2}",
+        ] {
+            assert!(
+                matches!(
+                    parser.parse_str(input).unwrap_err().details,
+                    ParserErrorDetails::DuplicateKey(_, _)
+                ),
+                "{}",
+                input
+            );
+        }
+        assert!(parser.parse_str("{\"a\" : 1, \"\\u0062\" : 2}").is_ok());
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_dialect(Dialect::Json5)
+                .with_duplicate_key_policy(DuplicateKeyPolicy::Error),
+        );
+        for input in ["{a : 1, 'a' : 2}", "{\"it's\" : 1, 'it\\'s' : 2}"] {
+            assert!(
+                matches!(
+                    parser.parse_str(input).unwrap_err().details,
+                    ParserErrorDetails::DuplicateKey(_, _)
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn should_fail_cleanly_on_deeply_nested_input() {
+        let parser = Parser::default();
+        for source in ["[".repeat(1_000_000), "{\"a\":".repeat(1_000_000)] {
+            let parsed = parser.parse_str(&source);
+            assert!(parsed.is_err());
+            let err = parsed.err().unwrap();
+            assert_eq!(
+                err.details,
+                ParserErrorDetails::MaxDepthExceeded(DEFAULT_MAX_DEPTH)
+            );
+            assert!(err.coords.is_some());
+        }
+    }
+
+    #[test]
+    fn should_respect_configured_max_depth() {
+        let parser = Parser::with_options(ParserOptions::default().with_max_depth(3));
+        let parsed = parser.parse_str("[[{\"a\" : []}]]");
+        assert_eq!(
+            parsed.err().unwrap().details,
+            ParserErrorDetails::MaxDepthExceeded(3)
+        );
+        let parsed = parser.parse_str("[[{\"a\" : 1}]]");
+        assert!(parsed.is_ok());
+    }
+
+    #[test]
+    fn should_enforce_member_and_node_limits() {
+        let limits = Limits {
+            max_members: Some(3),
+            max_nodes: Some(6),
+            ..Default::default()
+        };
+        let parser = Parser::with_options(ParserOptions::default().with_limits(limits));
+        assert!(parser.parse_str(r#"[1, 2, {"a" : 1, "b" : 2}]"#).is_ok());
+        assert_eq!(
+            parser.parse_str("[1, 2, 3, 4]").err().unwrap().details,
+            ParserErrorDetails::TooManyMembers(3)
+        );
+        assert_eq!(
+            parser
+                .parse_str(r#"{"a" : 1, "b" : 2, "c" : 3, "d" : 4}"#)
+                .err()
+                .unwrap()
+                .details,
+            ParserErrorDetails::TooManyMembers(3)
+        );
+        assert_eq!(
+            parser.parse_str("[[1, 2], [3, 4]]").err().unwrap().details,
+            ParserErrorDetails::TooManyNodes(6)
+        );
+    }
+
+    #[test]
+    fn should_retain_raw_numbers_when_required() {
+        let parser =
+            Parser::with_options(ParserOptions::default().with_numeric_mode(NumericMode::Raw));
+        match parser.parse_str("[1, -2.50, 3e+10]").unwrap() {
+            JsonValue::Array(values) => {
+                let raw: Vec<String> = values
+                    .iter()
+                    .map(|v| match v {
+                        JsonValue::RawNumber(n) => n.to_string(),
+                        _ => panic!(),
+                    })
+                    .collect();
+                assert_eq!(raw, ["1", "-2.50", "3e+10"]);
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn should_honour_encoding_for_all_entry_points() {
+        let ascii = fs::read(relative_file!("fixtures/json/encodings/ascii.json")).unwrap();
+        let utf8 = fs::read(relative_file!("fixtures/json/encodings/utf8.json")).unwrap();
+        for (encoding, accepts_utf8) in [(Encoding::Utf8, true), (Encoding::Ascii, false)] {
+            let parser = Parser::with_encoding(encoding);
+            for (input, ok) in [(&ascii, true), (&utf8, accepts_utf8)] {
+                assert_eq!(parser.parse_bytes(input).is_ok(), ok);
+                let str = std::str::from_utf8(input).unwrap();
+                assert_eq!(parser.parse_str(str).is_ok(), ok);
+                let mut buffer = BufReader::new(input.as_slice());
+                assert_eq!(parser.parse_buffer(&mut buffer).is_ok(), ok);
+            }
+            for (path, ok) in [
+                ("fixtures/json/encodings/ascii.json", true),
+                ("fixtures/json/encodings/utf8.json", accepts_utf8),
+            ] {
+                assert_eq!(parser.parse_file(relative_file!(path)).is_ok(), ok);
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_utf16_and_utf32_input() {
+        let expected = format!(
+            "{:?}",
+            Parser::default()
+                .parse_file(relative_file!("fixtures/json/encodings/utf8.json"))
+                .unwrap()
+        );
+        for (encoding, path) in [
+            (Encoding::Utf16Le, "fixtures/json/encodings/utf16le.json"),
+            (Encoding::Utf16Be, "fixtures/json/encodings/utf16be.json"),
+            (Encoding::Utf32Le, "fixtures/json/encodings/utf32le.json"),
+            (Encoding::Utf32Be, "fixtures/json/encodings/utf32be.json"),
+        ] {
+            let parser = Parser::with_encoding(encoding);
+            let parsed = parser.parse_file(relative_file!(path)).unwrap();
+            assert_eq!(format!("{:?}", parsed), expected);
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_byte_sequences_with_offsets() {
+        let mut bytes: Vec<u8> = "[\"a".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        bytes.extend_from_slice(&0xdc00u16.to_le_bytes());
+        bytes.extend_from_slice(&[b'"', 0, b']', 0]);
+        let err = Parser::with_encoding(Encoding::Utf16Le)
+            .parse_bytes(&bytes)
+            .err()
+            .unwrap();
+        assert_eq!(err.source, ParserErrorSource::Decoder);
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::InvalidByteSequence(Encoding::Utf16Le, 6)
+        );
+        assert_eq!(err.coords.unwrap().column, 3);
+    }
+
+    #[test]
+    fn should_detect_encoding_automatically() {
+        let parser = Parser::with_encoding(Encoding::Auto);
+        for (path, bom, encoding) in [
+            (
+                "fixtures/json/encodings/utf8.json",
+                &[0xef, 0xbb, 0xbf][..],
+                Encoding::Utf8,
+            ),
+            (
+                "fixtures/json/encodings/utf16le.json",
+                &[0xff, 0xfe][..],
+                Encoding::Utf16Le,
+            ),
+            (
+                "fixtures/json/encodings/utf16be.json",
+                &[0xfe, 0xff][..],
+                Encoding::Utf16Be,
+            ),
+            (
+                "fixtures/json/encodings/utf32le.json",
+                &[0xff, 0xfe, 0x00, 0x00][..],
+                Encoding::Utf32Le,
+            ),
+            (
+                "fixtures/json/encodings/utf32be.json",
+                &[0x00, 0x00, 0xfe, 0xff][..],
+                Encoding::Utf32Be,
+            ),
+        ] {
+            let bytes = fs::read(relative_file!(path)).unwrap();
+            let outcome = parser.parse_bytes_with_report(&bytes);
+            assert!(outcome.result.is_ok());
+            assert_eq!(outcome.report.encoding, Some(encoding));
+            let with_bom = [bom, bytes.as_slice()].concat();
+            let mut reader = BufReader::with_capacity(1, with_bom.as_slice());
+            let outcome = parser.parse_buffer_with_report(&mut reader);
+            assert!(outcome.result.is_ok());
+            assert_eq!(outcome.report.encoding, Some(encoding));
+        }
+        let mut chars = "[1]".chars();
+        assert!(parser
+            .parse_with_report(&mut chars)
+            .report
+            .encoding
+            .is_none());
+    }
+
+    #[test]
+    fn should_replace_invalid_utf8_when_lossy() {
+        let bytes = b"[\"a\xff\", \"\xfe\xfd\", \"\xef\xbf\xbd\"]";
+        assert!(Parser::default().parse_bytes(bytes).is_err());
+        let parser = Parser::with_encoding(Encoding::Utf8Lossy);
+        let outcome = parser.parse_bytes_with_report(bytes);
+        match outcome.result.unwrap() {
+            JsonValue::Array(values) => match &values[0] {
+                JsonValue::String(s) => assert_eq!(s, "\"a\u{fffd}\""),
+                _ => panic!(),
+            },
+            _ => panic!(),
+        }
+        let replacements = outcome.report.replacements.unwrap();
+        assert_eq!(replacements.count, 3);
+        assert_eq!((replacements.first.line, replacements.first.column), (1, 4));
+        let outcome = parser.parse_str_with_report("[\"\u{fffd}\"]");
+        assert!(outcome.result.is_ok());
+        assert!(outcome.report.replacements.is_none());
+    }
+
+    #[test]
+    fn should_preserve_io_errors_as_the_error_source() {
+        fn parse_missing() -> Result<(), Box<dyn std::error::Error>> {
+            Parser::default().parse_file("fixtures/json/does_not_exist.json")?;
+            Ok(())
+        }
+        let err = parse_missing().err().unwrap();
+        let parser_err = err.downcast_ref::<ParserError>().unwrap();
+        assert_eq!(parser_err.details, ParserErrorDetails::InvalidFile);
+        let cause = err.source().unwrap().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(cause.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn should_preserve_io_errors_raised_whilst_decoding() {
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::ConnectionReset, "reset"))
+            }
+        }
+        let mut buffer = BufReader::new(Failing);
+        let err = Parser::default().parse_buffer(&mut buffer).err().unwrap();
+        assert_eq!(err.source, ParserErrorSource::Decoder);
+        assert_eq!(err.details, ParserErrorDetails::StreamFailure);
+        let cause = std::error::Error::source(&err).unwrap();
+        assert_eq!(cause.to_string(), "reset");
+    }
+
+    #[test]
+    fn errors_should_include_the_pointer_of_the_failure() {
+        for (input, pointer) in [
+            (
+                "{\"items\" : [{\"price\" : 1}, {\"price\" : 2}, {\"price\" : x}]}",
+                Some("/items/2/price"),
+            ),
+            ("{\"a\" : [1, [2, 3, nul]]}", Some("/a/1/2")),
+            ("{\"a\" : 1, \"b\" 2}", Some("/b")),
+            ("[1, 2", Some("/1")),
+            ("x", None),
+        ] {
+            let err = Parser::default().parse_str(input).err().unwrap();
+            assert_eq!(err.pointer.map(|p| p.to_string()).as_deref(), pointer);
+        }
+    }
+
+    #[test]
+    fn should_recover_from_errors_when_enabled() {
+        let input = "{\"a\" : 1, \"b\" : x, \"c\" : [1, nul, 3], \"d\" {}, \"e\" : true}";
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_recovery(true),
+        );
+        let outcome = parser.parse_str_with_report(input);
+        match outcome.result.unwrap() {
+            JsonValue::Object(pairs) => {
+                assert_eq!(pairs.len(), 5);
+                assert!(matches!(pairs[0].1, JsonValue::Integer(1)));
+                assert!(matches!(pairs[1].1, JsonValue::Invalid));
+                assert!(matches!(
+                    &pairs[2].1,
+                    JsonValue::Array(values) if matches!(
+                        values[..],
+                        [JsonValue::Integer(1), JsonValue::Invalid, JsonValue::Integer(3)]
+                    )
+                ));
+                assert!(matches!(pairs[3].1, JsonValue::Invalid));
+                assert!(matches!(pairs[4].1, JsonValue::Boolean(true)));
+            }
+            _ => panic!("expected an object"),
+        }
+        let errors: Vec<(String, String)> = outcome
+            .report
+            .errors
+            .iter()
+            .map(|err| {
+                (
+                    err.details.code().to_string(),
+                    err.pointer.as_ref().unwrap().to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("E011".to_string(), "/b".to_string()),
+                ("E012".to_string(), "/c/1".to_string()),
+                ("E007".to_string(), "/d".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_collect_duplicate_keys_when_recovering() {
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_duplicate_key_policy(DuplicateKeyPolicy::Error)
+                .with_recovery(true),
+        );
+        let outcome = parser.parse_str_with_report("[{\"a\" : 1, \"a\" : 2}, 3]");
+        match outcome.result.unwrap() {
+            JsonValue::Array(values) => match &values[..] {
+                [JsonValue::Object(pairs), JsonValue::Integer(3)] => {
+                    assert_eq!(pairs.len(), 1);
+                    assert!(matches!(pairs[0].1, JsonValue::Integer(1)));
+                }
+                _ => panic!("expected an object followed by an integer"),
+            },
+            _ => panic!("expected an array"),
+        }
+        let errors = outcome.report.errors;
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(
+            errors[0].details,
+            ParserErrorDetails::DuplicateKey(_, _)
+        ));
+        assert_eq!(errors[0].pointer.as_ref().unwrap().to_string(), "/0");
+    }
+
+    #[test]
+    fn should_stop_at_unrecoverable_errors() {
+        let parser = Parser::with_options(ParserOptions::default().with_recovery(true));
+        let outcome = parser.parse_str_with_report("[1, x, 2");
+        let err = outcome.result.err().unwrap();
+        assert_eq!(err.details, ParserErrorDetails::EndOfInput);
+        let errors = outcome.report.errors;
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].details, ParserErrorDetails::InvalidCharacter('x'));
+        assert_eq!(errors[1].details, err.details);
+
+        let parser = Parser::default();
+        let outcome = parser.parse_str_with_report("[1, x, 2]");
+        let err = outcome.result.err().unwrap();
+        let errors = outcome.report.errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].details, err.details);
+    }
+
+    #[test]
+    fn error_coords_should_match_editors_for_all_line_endings() {
+        for (path, offset) in [
+            ("fixtures/json/line-endings/lf.json", 28),
+            ("fixtures/json/line-endings/crlf.json", 30),
+            ("fixtures/json/line-endings/cr.json", 28),
+        ] {
+            let err = Parser::default()
+                .parse_file(relative_file!(path))
+                .err()
+                .unwrap();
+            let coords = err.coords.unwrap();
+            assert_eq!((coords.line, coords.column, coords.offset), (3, 16, offset));
+        }
+    }
+
+    #[test]
+    fn should_skip_comments_when_allowed() {
+        let path = relative_file!("fixtures/json/jsonc/settings.jsonc");
+        let err = Parser::default().parse_file(&path).err().unwrap();
+        assert_eq!(err.details, ParserErrorDetails::InvalidCharacter('/'));
+        let options = ParserOptions::default()
+            .with_comment_policy(CommentPolicy::Skip)
+            .with_spans(true);
+        let parser = Parser::with_options(options);
+        let outcome = parser.parse_file_with_report(&path);
+        assert!(matches!(outcome.result.unwrap(), JsonValue::Object(pairs) if pairs.len() == 3));
+        let mut pointer = JsonPointer::default();
+        pointer.push_name("editor.rulers".to_string());
+        pointer.push_index(1);
+        let spans = outcome.report.spans.unwrap();
+        let start = spans.get(&pointer).unwrap().value.start;
+        assert_eq!((start.line, start.column), (5, 38));
+    }
+
+    #[test]
+    fn should_parse_json5_when_enabled() {
+        let path = relative_file!("fixtures/json/json5/config.json5");
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_dialect(Dialect::Json5),
+        );
+        let pairs = match parser.parse_file(&path).unwrap() {
+            JsonValue::Object(pairs) => pairs,
+            _ => panic!("expected an object"),
+        };
+        let keys: Vec<&str> = pairs.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "\"name\"",
+                "\"version\"",
+                "\"mask\"",
+                "\"ratios\"",
+                "\"limits\"",
+                "\"motto\""
+            ]
+        );
+        assert!(matches!(&pairs[0].1, JsonValue::String(name) if name == "\"chisel\""));
+        assert!(matches!(pairs[2].1, JsonValue::Integer(255)));
+        assert!(matches!(
+            &pairs[3].1,
+            JsonValue::Array(ratios) if matches!(
+                ratios[..],
+                [JsonValue::Float(a), JsonValue::Float(b), JsonValue::Integer(1)] if a == 0.5 && b == 5.0
+            )
+        ));
+        let limits: Vec<f64> = match &pairs[4].1 {
+            JsonValue::Object(limits) => limits
+                .iter()
+                .map(|(_, value)| match value {
+                    JsonValue::Float(f) => *f,
+                    _ => panic!("expected a float"),
+                })
+                .collect(),
+            _ => panic!("expected an object"),
+        };
+        assert_eq!(limits[..2], [f64::INFINITY, f64::NEG_INFINITY]);
+        assert!(limits[2].is_nan());
+        assert!(matches!(&pairs[5].1, JsonValue::String(motto) if motto == "\"It's a long line\""));
+
+        let value = parser
+            .parse_str("{null : 1, true : 2, NaN : 3, Infinity : [null]}")
+            .unwrap();
+        let keys: Vec<&str> = match &value {
+            JsonValue::Object(pairs) => pairs.iter().map(|(key, _)| key.as_str()).collect(),
+            _ => panic!("expected an object"),
+        };
+        assert_eq!(keys, ["\"null\"", "\"true\"", "\"NaN\"", "\"Infinity\""]);
+        assert_eq!(
+            parser.parse_str("[true, null, foo]").unwrap_err().details,
+            ParserErrorDetails::InvalidArray
+        );
+    }
+
+    #[test]
+    fn should_name_the_extension_required_by_strict_parsing() {
+        for (input, extension, column) in [
+            ("{port : 1}", Extension::UnquotedKeys, 2),
+            ("{\"a\" : 1, null : 2}", Extension::UnquotedKeys, 11),
+            ("['a']", Extension::SingleQuotedStrings, 2),
+            ("[1, 2, ]", Extension::TrailingCommas, 6),
+            ("{\"a\" : 1,}", Extension::TrailingCommas, 9),
+            ("[0x1F]", Extension::HexNumbers, 2),
+            ("[.5]", Extension::LeadingDecimalPoint, 2),
+            ("[+1]", Extension::PlusSign, 2),
+            ("[Infinity]", Extension::NonFiniteNumbers, 2),
+        ] {
+            let err = Parser::default().parse_str(input).unwrap_err();
+            assert_eq!(
+                err.details,
+                ParserErrorDetails::ExtensionRequired(extension),
+                "{}",
+                input
+            );
+            assert_eq!(err.coords.unwrap().column, column, "{}", input);
+        }
+        assert_eq!(
+            Parser::default().parse_str("[port]").unwrap_err().details,
+            ParserErrorDetails::InvalidCharacter('p')
+        );
+        for input in ["[1 2]", "[1,,2]", "[,1]", "{\"a\" : 1 \"b\" : 2}"] {
+            assert!(matches!(
+                Parser::default().parse_str(input).unwrap_err().details,
+                ParserErrorDetails::UnexpectedToken(_)
+            ));
+        }
+    }
+
+    #[test]
+    fn should_parse_non_finite_numbers_when_enabled() {
+        let input = "{\"mean\" : NaN, \"range\" : [-Infinity, Infinity], \"n\" : 3}";
+        let err = Parser::default().parse_str(input).unwrap_err();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::ExtensionRequired(Extension::NonFiniteNumbers)
+        );
+        assert!(err.to_string().contains("non-finite numbers option"));
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_non_finite_numbers(true),
+        );
+        let pairs = match parser.parse_str(input).unwrap() {
+            JsonValue::Object(pairs) => pairs,
+            _ => panic!("expected an object"),
+        };
+        assert!(matches!(pairs[0].1, JsonValue::Float(mean) if mean.is_nan()));
+        assert!(matches!(
+            &pairs[1].1,
+            JsonValue::Array(range) if matches!(
+                range[..],
+                [JsonValue::Float(lower), JsonValue::Float(upper)]
+                    if lower == f64::NEG_INFINITY && upper == f64::INFINITY
+            )
+        ));
+        assert!(matches!(pairs[2].1, JsonValue::Integer(3)));
+        for input in ["[-Infinity]", "[Infinity]"] {
+            assert_eq!(
+                Parser::default().parse_str(input).unwrap_err().details,
+                ParserErrorDetails::ExtensionRequired(Extension::NonFiniteNumbers)
+            );
+        }
+        for (input, extension) in [
+            ("[+1]", Extension::PlusSign),
+            ("[0x1]", Extension::HexNumbers),
+            ("['a']", Extension::SingleQuotedStrings),
+        ] {
+            assert_eq!(
+                parser.parse_str(input).unwrap_err().details,
+                ParserErrorDetails::ExtensionRequired(extension)
+            );
+        }
+        for input in ["[-In9finity]", "[-Na1N]"] {
+            assert!(
+                matches!(
+                    parser.parse_str(input).unwrap_err().details,
+                    ParserErrorDetails::InvalidNumericRepresentation(_)
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn should_tolerate_single_trailing_commas_when_enabled() {
+        let input = "{\"a\" : [1, 2, 3,], \"b\" : {\"c\" : 1,},}";
+        let err = Parser::default().parse_str(input).unwrap_err();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::ExtensionRequired(Extension::TrailingCommas)
+        );
+        assert!(err.to_string().contains("trailing commas option"));
+        let warnings = Arc::new(Mutex::new(vec![]));
+        let sink = warnings.clone();
+        let options = ParserOptions::default()
+            .with_trailing_commas(true)
+            .with_warning_hook(move |warning| sink.lock().unwrap().push(*warning));
+        let parser = Parser::with_options(options);
+        assert!(
+            matches!(parser.parse_str(input).unwrap(), JsonValue::Object(pairs) if pairs.len() == 2)
+        );
+        let columns: Vec<usize> = warnings
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|ParserWarning::TrailingComma(coords)| coords.column)
+            .collect();
+        assert_eq!(columns, [16, 34, 36]);
+        for input in ["[1,,]", "[1, 2,,]", "[,]", "{,}"] {
+            assert!(matches!(
+                parser.parse_str(input).unwrap_err().details,
+                ParserErrorDetails::UnexpectedToken(_)
+            ));
+        }
+        let caught = std::panic::catch_unwind(|| parser.parse_str("[1,]").is_ok());
+        assert!(caught.unwrap());
+    }
+
+    #[test]
+    fn should_parse_newline_delimited_records_lazily() {
+        let path = relative_file!("fixtures/json/ndjson/logs.ndjson");
+        let parser = Parser::default();
+        let mut records = parser.parse_lines(BufReader::new(File::open(&path).unwrap()));
+        assert_eq!(records.next().unwrap().unwrap().line, 1);
+        assert_eq!(records.next().unwrap().unwrap().index, 1);
+        let err = records.next().unwrap().unwrap_err();
+        let coords = err.coords.unwrap();
+        assert_eq!((coords.line, coords.offset), (4, 100));
+        assert!(records.next().is_none());
+
+        let parser = Parser::with_options(ParserOptions::default().with_skip_invalid_records(true));
+        let mut records = parser.parse_lines(BufReader::new(File::open(&path).unwrap()));
+        let positions: Vec<(usize, usize)> = records
+            .by_ref()
+            .map(|record| {
+                let record = record.unwrap();
+                (record.index, record.line)
+            })
+            .collect();
+        assert_eq!(positions, [(0, 1), (1, 2), (3, 5), (4, 6)]);
+        assert_eq!(records.skipped().len(), 1);
+        assert_eq!(records.skipped()[0].coords.unwrap().line, 4);
+    }
+
+    #[test]
+    fn should_accept_scalar_records() {
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_spans(true),
+        );
+        let values: Vec<String> = parser
+            .parse_lines("1\n\"x\"\ntrue\nnull\n-2.5\n{\"a\" : 1}\n".as_bytes())
+            .map(|record| match record.unwrap().value {
+                JsonValue::Integer(value) => value.to_string(),
+                JsonValue::String(value) => value.to_string(),
+                JsonValue::Boolean(value) => value.to_string(),
+                JsonValue::Float(value) => value.to_string(),
+                JsonValue::Null => "null".to_string(),
+                JsonValue::Object(_) => "{}".to_string(),
+                _ => panic!("unexpected value"),
+            })
+            .collect();
+        assert_eq!(values, ["1", "\"x\"", "true", "null", "-2.5", "{}"]);
+        for input in ["1", "\"x\"", "true"] {
+            let err = parser.parse_str(input).unwrap_err();
+            assert_eq!(err.details, ParserErrorDetails::InvalidRootObject);
+        }
+    }
+
+    #[test]
+    fn should_reject_line_encodings_which_are_not_ascii_compatible() {
+        let bytes: Vec<u8> = "{\"a\":1}\n{\"b\":2}\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        for encoding in [Encoding::Utf16Le, Encoding::Auto] {
+            let parser = Parser::with_encoding(encoding);
+            let mut records = parser.parse_lines(bytes.as_slice());
+            let err = records.next().unwrap().unwrap_err();
+            assert_eq!(
+                err.details,
+                ParserErrorDetails::UnsupportedLineEncoding(encoding)
+            );
+            assert!(records.next().is_none());
+        }
+        let latin1 = b"[\"\xa9 caf\xe9\"]\n[\"na\xefve\", x]\n";
+        let parser = Parser::with_encoding(Encoding::Latin1);
+        let mut records = parser.parse_lines(&latin1[..]);
+        assert!(records.next().unwrap().is_ok());
+        let coords = records.next().unwrap().unwrap_err().coords.unwrap();
+        assert_eq!((coords.line, coords.absolute), (2, 22));
+    }
+
+    #[test]
+    fn records_should_carry_the_errors_recovered_from() {
+        let parser = Parser::with_options(ParserOptions::default().with_recovery(true));
+        let mut records = parser.parse_lines("[1]\n\n[2, x, 3]\n".as_bytes());
+        assert!(records.next().unwrap().unwrap().errors.is_empty());
+        let errors = records.next().unwrap().unwrap().errors;
+        assert_eq!(errors.len(), 1);
+        let coords = errors[0].coords.unwrap();
+        assert_eq!((coords.line, coords.column), (3, 5));
+    }
+
+    #[test]
+    fn should_read_concatenated_values_from_a_single_stream() {
+        let parser = Parser::default();
+        let mut input = "{\"a\" : 1}{\"b\" : 2}\n  [3]  \t[4, {\"c\" : null}]\n".as_bytes();
+        let lines: Vec<(usize, usize)> = parser
+            .parse_sequence(&mut input)
+            .map(|record| {
+                let record = record.unwrap();
+                (record.index, record.line)
+            })
+            .collect();
+        assert_eq!(lines, [(0, 1), (1, 1), (2, 2), (3, 2)]);
+        let mut input = "{\"a\" : 1} [1, 2".as_bytes();
+        let mut values = parser.parse_sequence(&mut input);
+        assert!(values.next().unwrap().is_ok());
+        let err = values.next().unwrap().unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::TruncatedRecord);
+        assert!(values.next().is_none());
+    }
+
+    #[test]
+    fn should_skip_truncated_records_within_text_sequences() {
+        let input = concat!(
+            "\u{1e}{\"a\" : 1}\n",
+            "\u{1e}{\"b\" : \"cut\u{1e}[\"ok\"]\n",
+            "\u{1e}[1, 2\n",
+            "\u{1e}{\"c\" : x}\n",
+            "\u{1e}[true]\n",
+            "\u{1e}{\"d\" : ["
+        );
+        let parser = Parser::default();
+        let mut bytes = input.as_bytes();
+        let outcomes: Vec<Result<(usize, usize), ParserErrorDetails>> = parser
+            .parse_sequence(&mut bytes)
+            .map(|record| {
+                record
+                    .map(|record| (record.index, record.line))
+                    .map_err(|err| err.details)
+            })
+            .collect();
+        assert_eq!(
+            outcomes,
+            [
+                Ok((0, 1)),
+                Err(ParserErrorDetails::TruncatedRecord),
+                Ok((2, 2)),
+                Err(ParserErrorDetails::TruncatedRecord),
+                Err(ParserErrorDetails::InvalidCharacter('x')),
+                Ok((5, 5)),
+                Err(ParserErrorDetails::TruncatedRecord),
+            ]
+        );
+        assert!(Parser::default().parse_str("\u{1e}[1]").is_err());
+    }
+
+    #[test]
+    fn should_record_the_spans_of_values_and_keys() {
+        let input = "{\n  \"a\" : [1, {\"b\" : null}],\n  \"c\" : \"x\"\n}";
+        let parser = Parser::with_options(ParserOptions::default().with_spans(true));
+        let outcome = parser.parse_str_with_report(input);
+        assert!(outcome.result.is_ok());
+        let spans = outcome.report.spans.unwrap();
+        assert_eq!(spans.len(), 6);
+        let locate = |path: &str| {
+            let mut pointer = JsonPointer::default();
+            for component in path.split('/').skip(1) {
+                match component.parse::<usize>() {
+                    Ok(index) => pointer.push_index(index),
+                    Err(_) => pointer.push_name(component.to_string()),
+                }
+            }
+            let node = spans.get(&pointer).unwrap();
+            (
+                node.key.map(|key| (key.start.line, key.start.column)),
+                &input[node.value.byte_range()],
+            )
+        };
+        assert_eq!(locate(""), (None, input));
+        assert_eq!(locate("/a"), (Some((2, 3)), "[1, {\"b\" : null}]"));
+        assert_eq!(locate("/a/0"), (None, "1"));
+        assert_eq!(locate("/a/1"), (None, "{\"b\" : null}"));
+        assert_eq!(locate("/a/1/b"), (Some((2, 14)), "null"));
+        assert_eq!(locate("/c"), (Some((3, 3)), "\"x\""));
+    }
+
+    #[test]
+    fn spans_should_follow_duplicate_key_policies() {
+        let input = "{\"a\" : 1, \"a\" : 22}";
+        for (policy, value) in [
+            (DuplicateKeyPolicy::FirstWins, "1"),
+            (DuplicateKeyPolicy::LastWins, "22"),
+        ] {
+            let parser = Parser::with_options(
+                ParserOptions::default()
+                    .with_duplicate_key_policy(policy)
+                    .with_spans(true),
+            );
+            let outcome = parser.parse_str_with_report(input);
+            assert!(outcome.result.is_ok());
+            let mut pointer = JsonPointer::default();
+            pointer.push_name("a".to_string());
+            let spans = outcome.report.spans.unwrap();
+            assert_eq!(
+                &input[spans.get(&pointer).unwrap().value.byte_range()],
+                value
+            );
+        }
+        let parser = Parser::default();
+        assert!(parser.parse_str_with_report(input).report.spans.is_none());
+    }
 }
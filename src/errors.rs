@@ -3,31 +3,45 @@
 //! This module contains definitions for the main [Result] types used throughout the parser.
 
 use crate::coords::Coords;
+use crate::decoders::Encoding;
 use crate::lexer::Token;
+use crate::options::Extension;
+use crate::pointer::JsonPointer;
 use std::borrow::Cow;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::io::BufRead;
+use std::sync::Arc;
 
 /// Global result type used throughout the parser stages
 pub type ParserResult<T> = Result<T, ParserError>;
 
 /// Enumeration of the various different parser stages that can produce an error
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ParserErrorSource {
+    /// The decoding stage of the parser
+    Decoder,
     /// The lexing stage of the parser
     Lexer,
     /// The parsing stage of the DOM parser
     DomParser,
     /// The parsing stage of the SAX parser
     SaxParser,
+    /// The parsing and editing of a concrete syntax tree
+    Cst,
+    /// The writing of a [crate::JsonValue] as JSON text
+    Writer,
 }
 
 impl Display for ParserErrorSource {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
+            ParserErrorSource::Decoder => write!(f, "decoding"),
             ParserErrorSource::Lexer => write!(f, "lexing"),
             ParserErrorSource::DomParser => write!(f, "DOM parsing"),
             ParserErrorSource::SaxParser => write!(f, "SAX parsing"),
+            ParserErrorSource::Cst => write!(f, "CST parsing"),
+            ParserErrorSource::Writer => write!(f, "writing"),
         }
     }
 }
@@ -46,6 +60,9 @@ pub enum ParserErrorDetails {
     StreamFailure,
     /// Dodgy UTF8 has been found in the input.
     NonUtf8InputDetected,
+    /// An invalid byte sequence for a given [Encoding] has been found in the input. Carries the
+    /// byte offset of the start of the sequence.
+    InvalidByteSequence(Encoding, usize),
     /// Edge case error condition. This means that something has gone horribly wrong with the
     /// parse.
     UnexpectedToken(Token),
@@ -67,6 +84,38 @@ pub enum ParserErrorDetails {
     InvalidEscapeSequence(String),
     /// An invalid unicode escape sequence (\uXXX) has been found within the input.
     InvalidUnicodeEscapeSequence(String),
+    /// A key has been repeated within an object. Carries the key, along with the (boxed) [Coords]
+    /// of the first and repeated occurrences. Keys are compared after decoding any escapes, so
+    /// `"a"` and `"\u0061"` name the same member
+    DuplicateKey(String, Box<(Coords, Coords)>),
+    /// The nesting of objects and arrays within the input has exceeded the configured maximum
+    /// depth.
+    MaxDepthExceeded(usize),
+    /// The input has exceeded the configured maximum length.
+    InputTooLarge(usize),
+    /// A string has exceeded the configured maximum length.
+    StringTooLong(usize),
+    /// The representation of a number has exceeded the configured maximum length.
+    NumberTooLong(usize),
+    /// An object or array has exceeded the configured maximum number of members.
+    TooManyMembers(usize),
+    /// The input has exceeded the configured maximum number of values.
+    TooManyNodes(usize),
+    /// No value could be found at a given pointer.
+    PointerNotFound(String),
+    /// An edit can't be applied to a concrete syntax tree.
+    InvalidEdit(String),
+    /// A block comment hasn't been closed before the end of the input.
+    UnterminatedComment,
+    /// The input makes use of an [Extension] to JSON which hasn't been enabled.
+    ExtensionRequired(Extension),
+    /// A record within a stream of records ended before its value was complete.
+    TruncatedRecord,
+    /// Newline-delimited records can't be read using the given encoding, as line feeds can only
+    /// be found reliably within ASCII-compatible encodings.
+    UnsupportedLineEncoding(Encoding),
+    /// A [crate::JsonValue::Invalid] placeholder has no JSON representation, so can't be written.
+    InvalidValue,
 }
 
 impl Display for ParserErrorDetails {
@@ -77,6 +126,11 @@ impl Display for ParserErrorDetails {
             ParserErrorDetails::EndOfInput => write!(f, "end of input reached"),
             ParserErrorDetails::StreamFailure => write!(f, "failure in the underlying stream"),
             ParserErrorDetails::NonUtf8InputDetected => write!(f, "non-UTF8 input"),
+            ParserErrorDetails::InvalidByteSequence(encoding, offset) => write!(
+                f,
+                "invalid {} byte sequence at byte offset {}",
+                encoding, offset
+            ),
             ParserErrorDetails::UnexpectedToken(token) => {
                 write!(f, "unexpected token found: {}", token)
             }
@@ -101,6 +155,174 @@ impl Display for ParserErrorDetails {
             ParserErrorDetails::InvalidUnicodeEscapeSequence(seq) => {
                 write!(f, "invalid unicode escape sequence: \"{}\"", seq)
             }
+            ParserErrorDetails::DuplicateKey(key, occurrences) => {
+                write!(
+                    f,
+                    "duplicate key {}, first occurrence at {}",
+                    key, occurrences.0
+                )
+            }
+            ParserErrorDetails::MaxDepthExceeded(depth) => {
+                write!(f, "maximum nesting depth of {} exceeded", depth)
+            }
+            ParserErrorDetails::InputTooLarge(max) => {
+                write!(f, "input exceeds maximum length of {} bytes", max)
+            }
+            ParserErrorDetails::StringTooLong(max) => {
+                write!(f, "string exceeds maximum length of {} characters", max)
+            }
+            ParserErrorDetails::NumberTooLong(max) => {
+                write!(f, "number exceeds maximum length of {} characters", max)
+            }
+            ParserErrorDetails::TooManyMembers(max) => {
+                write!(f, "object or array exceeds maximum of {} members", max)
+            }
+            ParserErrorDetails::TooManyNodes(max) => {
+                write!(f, "input exceeds maximum of {} values", max)
+            }
+            ParserErrorDetails::PointerNotFound(pointer) => {
+                write!(f, "no value found at pointer \"{}\"", pointer)
+            }
+            ParserErrorDetails::InvalidEdit(reason) => write!(f, "invalid edit: {}", reason),
+            ParserErrorDetails::UnterminatedComment => write!(f, "unterminated block comment"),
+            ParserErrorDetails::ExtensionRequired(extension) => write!(
+                f,
+                "{} aren't allowed in strict JSON, but may be enabled by {}",
+                extension,
+                extension.enabled_by()
+            ),
+            ParserErrorDetails::TruncatedRecord => write!(f, "truncated record"),
+            ParserErrorDetails::UnsupportedLineEncoding(encoding) => write!(
+                f,
+                "newline-delimited records can't be read using the {} encoding",
+                encoding
+            ),
+            ParserErrorDetails::InvalidValue => {
+                write!(f, "placeholder for an invalid value can't be written")
+            }
+        }
+    }
+}
+
+impl ParserErrorDetails {
+    /// A stable, unique code for each kind of error. Codes are never reused, so may be relied upon
+    /// in documentation and tooling
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserErrorDetails::InvalidFile => "E001",
+            ParserErrorDetails::ZeroLengthInput => "E002",
+            ParserErrorDetails::EndOfInput => "E003",
+            ParserErrorDetails::StreamFailure => "E004",
+            ParserErrorDetails::NonUtf8InputDetected => "E005",
+            ParserErrorDetails::UnexpectedToken(_) => "E006",
+            ParserErrorDetails::PairExpected => "E007",
+            ParserErrorDetails::InvalidRootObject => "E008",
+            ParserErrorDetails::InvalidObject => "E009",
+            ParserErrorDetails::InvalidArray => "E010",
+            ParserErrorDetails::InvalidCharacter(_) => "E011",
+            ParserErrorDetails::MatchFailed(_, _) => "E012",
+            ParserErrorDetails::InvalidNumericRepresentation(_) => "E013",
+            ParserErrorDetails::InvalidEscapeSequence(_) => "E014",
+            ParserErrorDetails::InvalidUnicodeEscapeSequence(_) => "E015",
+            ParserErrorDetails::DuplicateKey(_, _) => "E016",
+            ParserErrorDetails::MaxDepthExceeded(_) => "E017",
+            ParserErrorDetails::InputTooLarge(_) => "E018",
+            ParserErrorDetails::StringTooLong(_) => "E019",
+            ParserErrorDetails::NumberTooLong(_) => "E020",
+            ParserErrorDetails::TooManyMembers(_) => "E021",
+            ParserErrorDetails::TooManyNodes(_) => "E022",
+            ParserErrorDetails::InvalidByteSequence(_, _) => "E023",
+            ParserErrorDetails::PointerNotFound(_) => "E024",
+            ParserErrorDetails::InvalidEdit(_) => "E025",
+            ParserErrorDetails::UnterminatedComment => "E026",
+            ParserErrorDetails::ExtensionRequired(_) => "E027",
+            ParserErrorDetails::TruncatedRecord => "E028",
+            ParserErrorDetails::UnsupportedLineEncoding(_) => "E029",
+            ParserErrorDetails::InvalidValue => "E030",
+        }
+    }
+
+    /// A short hint as to how the error might be fixed, where one is available
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ParserErrorDetails::EndOfInput => Some("did you forget a closing '}' or ']'?"),
+            ParserErrorDetails::UnexpectedToken(Token::EndObject | Token::EndArray) => {
+                Some("is there a trailing comma before this?")
+            }
+            ParserErrorDetails::UnexpectedToken(Token::Identifier(_)) => {
+                Some("unquoted identifiers may only be used as object keys")
+            }
+            ParserErrorDetails::UnexpectedToken(_) => Some("did you forget a comma?"),
+            ParserErrorDetails::PairExpected => {
+                Some("did you forget the ':' between a key and its value?")
+            }
+            ParserErrorDetails::InvalidRootObject => {
+                Some("the input must start with either an object or an array")
+            }
+            ParserErrorDetails::InvalidObject => {
+                Some("object members take the form \"key\" : value, and are separated by commas")
+            }
+            ParserErrorDetails::InvalidArray => {
+                Some("array elements must be values, and are separated by commas")
+            }
+            ParserErrorDetails::InvalidCharacter('\'') => {
+                Some("strings must be enclosed in double quotes")
+            }
+            ParserErrorDetails::InvalidCharacter('/') => {
+                Some("comments are only allowed when enabled through the parser's options")
+            }
+            ParserErrorDetails::ExtensionRequired(_) => {
+                Some("extensions to JSON may be enabled through the parser's options")
+            }
+            ParserErrorDetails::UnterminatedComment => {
+                Some("block comments must be closed with '*/'")
+            }
+            ParserErrorDetails::TruncatedRecord => {
+                Some("each record must hold a complete value before the next record begins")
+            }
+            ParserErrorDetails::UnsupportedLineEncoding(_) => {
+                Some("use UTF-8 (or another ASCII-compatible encoding) for newline-delimited JSON")
+            }
+            ParserErrorDetails::InvalidValue => {
+                Some("replace or remove any values which failed to parse before writing")
+            }
+            ParserErrorDetails::InvalidCharacter(_) => {
+                Some("did you forget to enclose a string in double quotes?")
+            }
+            ParserErrorDetails::MatchFailed(_, _) => {
+                Some("literals must be one of null, true or false, in lower case")
+            }
+            ParserErrorDetails::InvalidNumericRepresentation(_) => {
+                Some("check the number for misplaced signs, leading zeros, or a missing fraction or exponent")
+            }
+            ParserErrorDetails::InvalidEscapeSequence(_) => {
+                Some("valid escapes are \\\", \\\\, \\/, \\b, \\f, \\n, \\r, \\t and \\uXXXX")
+            }
+            ParserErrorDetails::InvalidUnicodeEscapeSequence(_) => {
+                Some("unicode escapes take the form \\u followed by four hex digits")
+            }
+            ParserErrorDetails::DuplicateKey(_, _) => {
+                Some("keys should be unique within an object")
+            }
+            ParserErrorDetails::MaxDepthExceeded(_)
+            | ParserErrorDetails::InputTooLarge(_)
+            | ParserErrorDetails::StringTooLong(_)
+            | ParserErrorDetails::NumberTooLong(_)
+            | ParserErrorDetails::TooManyMembers(_)
+            | ParserErrorDetails::TooManyNodes(_) => {
+                Some("limits may be raised through the parser's options")
+            }
+            ParserErrorDetails::NonUtf8InputDetected
+            | ParserErrorDetails::InvalidByteSequence(_, _) => {
+                Some("check the encoding configured for the parser")
+            }
+            ParserErrorDetails::PointerNotFound(_) => {
+                Some("pointers are made up of object keys and array indexes, e.g. /servers/0/port")
+            }
+            ParserErrorDetails::InvalidFile
+            | ParserErrorDetails::ZeroLengthInput
+            | ParserErrorDetails::StreamFailure
+            | ParserErrorDetails::InvalidEdit(_) => None,
         }
     }
 }
@@ -114,24 +336,123 @@ pub struct ParserError {
     pub details: ParserErrorDetails,
     /// Parser [Coords]
     pub coords: Option<Coords>,
+    /// The underlying I/O error (if any) which caused the error
+    pub cause: Option<Arc<std::io::Error>>,
+    /// The [JsonPointer] of the value being parsed when the error occurred. This is absent for
+    /// errors which occur outside of the root object or array
+    pub pointer: Option<Box<JsonPointer<'static>>>,
 }
 
-impl Display for ParserError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        if self.coords.is_some() {
-            write!(
-                f,
-                "Source: {}, Details: {}, Coords: {}",
-                self.source,
+impl ParserError {
+    /// Attach the [JsonPointer] of the value being parsed, unless a pointer is already present
+    pub(crate) fn with_pointer(mut self, pointer: &JsonPointer) -> Self {
+        if self.pointer.is_none() && !pointer.is_empty() {
+            self.pointer = Some(Box::new(pointer.clone().into_owned()));
+        }
+        self
+    }
+
+    /// Check whether parsing may continue after this error when recovery is enabled. Errors
+    /// raised by the decoder, at the end of the input, or by exceeding a limit are never
+    /// recoverable
+    pub(crate) fn is_recoverable(&self) -> bool {
+        self.source != ParserErrorSource::Decoder
+            && matches!(
                 self.details,
-                self.coords.unwrap()
+                ParserErrorDetails::UnexpectedToken(_)
+                    | ParserErrorDetails::PairExpected
+                    | ParserErrorDetails::InvalidObject
+                    | ParserErrorDetails::InvalidArray
+                    | ParserErrorDetails::InvalidCharacter(_)
+                    | ParserErrorDetails::MatchFailed(_, _)
+                    | ParserErrorDetails::InvalidNumericRepresentation(_)
+                    | ParserErrorDetails::InvalidEscapeSequence(_)
+                    | ParserErrorDetails::InvalidUnicodeEscapeSequence(_)
+                    | ParserErrorDetails::DuplicateKey(_, _)
+                    | ParserErrorDetails::ExtensionRequired(_)
             )
-        } else {
-            write!(f, "Source: {}, Details: {}", self.source, self.details)
+    }
+
+    /// Prefix the [JsonPointer] of the error with the name of an enclosing object member
+    pub(crate) fn within_name(mut self, name: &str) -> Self {
+        self.prefix_name(name);
+        self
+    }
+
+    /// Prefix the [JsonPointer] of the error with the index of an enclosing array element
+    pub(crate) fn within_index(mut self, index: usize) -> Self {
+        self.prefix_index(index);
+        self
+    }
+
+    /// In-place version of [ParserError::within_name]
+    pub(crate) fn prefix_name(&mut self, name: &str) {
+        self.pointer
+            .get_or_insert_with(Default::default)
+            .push_front_name(name.replace('\"', ""));
+    }
+
+    /// In-place version of [ParserError::within_index]
+    pub(crate) fn prefix_index(&mut self, index: usize) {
+        self.pointer
+            .get_or_insert_with(Default::default)
+            .push_front_index(index);
+    }
+}
+
+impl Display for ParserError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Source: {}, Details: {}", self.source, self.details)?;
+        if let Some(coords) = self.coords {
+            write!(f, ", Coords: {}", coords)?;
         }
+        if let Some(pointer) = &self.pointer {
+            write!(f, ", Pointer: {}", pointer)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ParserError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.cause
+            .as_deref()
+            .map(|cause| cause as &(dyn Error + 'static))
     }
 }
 
+/// Helper macro for cooking up a [ParserError] specific to the decoding stage
+#[macro_export]
+macro_rules! decoder_error {
+    ($details: expr, cause = $cause: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Decoder,
+            details: $details,
+            coords: None,
+            cause: Some(std::sync::Arc::new($cause)),
+            pointer: None,
+        })
+    };
+    ($details: expr, $coords: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Decoder,
+            details: $details,
+            coords: Some($coords),
+            cause: None,
+            pointer: None,
+        })
+    };
+    ($details: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Decoder,
+            details: $details,
+            coords: None,
+            cause: None,
+            pointer: None,
+        })
+    };
+}
+
 /// Helper macro for cooking up a [ParserError] specific to the lexer
 #[macro_export]
 macro_rules! lexer_error {
@@ -140,6 +461,8 @@ macro_rules! lexer_error {
             source: ParserErrorSource::Lexer,
             details: $details,
             coords: Some($coords),
+            cause: None,
+            pointer: None,
         })
     };
     ($details: expr) => {
@@ -147,6 +470,8 @@ macro_rules! lexer_error {
             source: ParserErrorSource::Lexer,
             details: $details,
             coords: None,
+            cause: None,
+            pointer: None,
         })
     };
 }
@@ -154,11 +479,22 @@ macro_rules! lexer_error {
 /// Helper macro for cooking up a [ParserError] specific to the DOM parser
 #[macro_export]
 macro_rules! dom_parser_error {
+    ($details: expr, cause = $cause: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::DomParser,
+            details: $details,
+            coords: None,
+            cause: Some(std::sync::Arc::new($cause)),
+            pointer: None,
+        })
+    };
     ($details: expr, $coords: expr) => {
         Err(ParserError {
             source: ParserErrorSource::DomParser,
             details: $details,
             coords: Some($coords),
+            cause: None,
+            pointer: None,
         })
     };
     ($details: expr) => {
@@ -166,6 +502,8 @@ macro_rules! dom_parser_error {
             source: ParserErrorSource::DomParser,
             details: $details,
             coords: None,
+            cause: None,
+            pointer: None,
         })
     };
 }
@@ -173,11 +511,22 @@ macro_rules! dom_parser_error {
 /// Helper macro for cooking up a [ParserError] specific to the SAX parser
 #[macro_export]
 macro_rules! sax_parser_error {
+    ($details: expr, cause = $cause: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::SaxParser,
+            details: $details,
+            coords: None,
+            cause: Some(std::sync::Arc::new($cause)),
+            pointer: None,
+        })
+    };
     ($details: expr, $coords: expr) => {
         Err(ParserError {
             source: ParserErrorSource::SaxParser,
             details: $details,
             coords: Some($coords),
+            cause: None,
+            pointer: None,
         })
     };
     ($details: expr) => {
@@ -185,6 +534,63 @@ macro_rules! sax_parser_error {
             source: ParserErrorSource::SaxParser,
             details: $details,
             coords: None,
+            cause: None,
+            pointer: None,
+        })
+    };
+}
+
+/// Helper macro for cooking up a [ParserError] specific to the CST parser
+#[macro_export]
+macro_rules! cst_error {
+    ($details: expr, cause = $cause: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Cst,
+            details: $details,
+            coords: None,
+            cause: Some(std::sync::Arc::new($cause)),
+            pointer: None,
+        })
+    };
+    ($details: expr, $coords: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Cst,
+            details: $details,
+            coords: Some($coords),
+            cause: None,
+            pointer: None,
+        })
+    };
+    ($details: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Cst,
+            details: $details,
+            coords: None,
+            cause: None,
+            pointer: None,
+        })
+    };
+}
+
+/// Helper macro for cooking up a [ParserError] specific to the writer
+#[macro_export]
+macro_rules! writer_error {
+    ($details: expr, cause = $cause: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Writer,
+            details: $details,
+            coords: None,
+            cause: Some(std::sync::Arc::new($cause)),
+            pointer: None,
+        })
+    };
+    ($details: expr) => {
+        Err(ParserError {
+            source: ParserErrorSource::Writer,
+            details: $details,
+            coords: None,
+            cause: None,
+            pointer: None,
         })
     };
 }
@@ -8,7 +8,7 @@ use std::borrow::Cow;
 use std::fmt::Display;
 
 /// Enumeration of the various different matches that can be produced during a parse
-#[derive(PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Match<'a> {
     /// Start of the input Emitted prior to anything else
     StartOfInput,
@@ -30,10 +30,37 @@ pub enum Match<'a> {
     Integer(i64),
     /// Emitted when a float is matched
     Float(f64),
+    /// Emitted when a number is matched and [crate::options::NumericMode::Raw] is in use
+    RawNumber(Cow<'a, str>),
     /// Emitted when a boolean is matched
     Boolean(bool),
     /// Emitted when a null is matched
     Null,
+    /// Emitted when a comment is matched and [crate::options::CommentPolicy::Report] is in use.
+    /// Contains the full text of the comment, including the `//` or `/* */` delimiters
+    Comment(Cow<'a, str>),
+}
+
+impl<'a> Match<'a> {
+    /// Convert into a [Match] which owns any underlying string content
+    pub fn into_owned(self) -> Match<'static> {
+        match self {
+            Match::StartOfInput => Match::StartOfInput,
+            Match::EndOfInput => Match::EndOfInput,
+            Match::StartObject => Match::StartObject,
+            Match::ObjectKey(key) => Match::ObjectKey(Cow::Owned(key.into_owned())),
+            Match::EndObject => Match::EndObject,
+            Match::StartArray => Match::StartArray,
+            Match::EndArray => Match::EndArray,
+            Match::String(value) => Match::String(Cow::Owned(value.into_owned())),
+            Match::Integer(value) => Match::Integer(value),
+            Match::Float(value) => Match::Float(value),
+            Match::RawNumber(value) => Match::RawNumber(Cow::Owned(value.into_owned())),
+            Match::Boolean(b) => Match::Boolean(b),
+            Match::Null => Match::Null,
+            Match::Comment(text) => Match::Comment(Cow::Owned(text.into_owned())),
+        }
+    }
 }
 
 impl<'a> Display for Match<'a> {
@@ -49,8 +76,10 @@ impl<'a> Display for Match<'a> {
             Match::String(value) => write!(f, "String({})", value),
             Match::Integer(value) => write!(f, "Integer({})", value),
             Match::Float(value) => write!(f, "Float({})", value),
+            Match::RawNumber(value) => write!(f, "RawNumber({})", value),
             Match::Boolean(b) => write!(f, "Boolean({})", b),
             Match::Null => write!(f, "Null"),
+            Match::Comment(text) => write!(f, "Comment({})", text),
         }
     }
 }
@@ -76,14 +105,8 @@ impl<'a> Event<'a> {
 
 impl<'a> Display for Event<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if self.pointer.is_some() {
-            write!(
-                f,
-                "Event[{}, {}, {}]",
-                self.matched,
-                self.span,
-                self.pointer.unwrap()
-            )
+        if let Some(pointer) = self.pointer {
+            write!(f, "Event[{}, {}, {}]", self.matched, self.span, pointer)
         } else {
             write!(f, "Event[{}, {}]", self.matched, self.span)
         }
@@ -3,13 +3,15 @@
 #![allow(unused_variables)]
 #![allow(unreachable_code)]
 use crate::coords::{Coords, Span};
+use crate::decoders::{Chars, Decoder, Encoding, Replacements};
 use crate::dom::Parser;
+use crate::options::{
+    CommentPolicy, Dialect, Extension, Extensions, Limits, NumericMode, ParserOptions,
+};
 use crate::{
     errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult},
     lexer_error,
 };
-use chisel_decoders::common::{DecoderError, DecoderErrorCode, DecoderResult};
-use chisel_decoders::utf8::Utf8Decoder;
 use std::borrow::Cow;
 use std::cell::{Cell, RefCell};
 use std::fmt::{Display, Formatter};
@@ -25,6 +27,9 @@ const TRUE_PATTERN: [char; 4] = ['t', 'r', 'u', 'e'];
 /// Pattern to match for false
 const FALSE_PATTERN: [char; 5] = ['f', 'a', 'l', 's', 'e'];
 
+/// The RFC 7464 record separator, which precedes each record within a JSON text sequence
+const RECORD_SEPARATOR: char = '\u{1e}';
+
 /// Enumeration of valid JSON tokens
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
@@ -35,10 +40,16 @@ pub enum Token {
     Colon,
     Comma,
     Str(String),
+    /// An identifier used as an unquoted object key, enclosed in double quotes so that it takes
+    /// the same form as a [Token::Str]
+    Identifier(String),
     Float(f64),
     Integer(i64),
+    RawNumber(String),
     Null,
     Boolean(bool),
+    /// An RFC 7464 record separator (`0x1E`), only produced when reading a sequence of records
+    RecordSeparator,
     EndOfInput,
 }
 
@@ -52,10 +63,13 @@ impl Display for Token {
             Token::Colon => write!(f, "Colon"),
             Token::Comma => write!(f, "Comma"),
             Token::Str(str) => write!(f, "String(\"{}\")", str),
+            Token::Identifier(name) => write!(f, "Identifier({})", name),
             Token::Float(num) => write!(f, "Float({})", num),
             Token::Integer(num) => write!(f, "Integer({})", num),
+            Token::RawNumber(num) => write!(f, "RawNumber({})", num),
             Token::Null => write!(f, "Null"),
             Token::Boolean(bool) => write!(f, "Boolean({})", bool),
+            Token::RecordSeparator => write!(f, "RecordSeparator"),
             Token::EndOfInput => write!(f, "EndOfInput"),
         }
     }
@@ -66,11 +80,25 @@ pub type PackedToken<'a> = (Token, Span);
 
 /// Convenience macro for packing tokens along with their positional information
 macro_rules! packed_token {
-    ($t:expr, $s:expr, $e:expr) => {
-        Ok(($t, Span { start: $s, end: $e }))
+    ($l:expr, $t:expr, $s:expr, $e:expr) => {
+        Ok((
+            $t,
+            Span {
+                start: $s,
+                end: $e,
+                end_offset: $l.end_offset(),
+            },
+        ))
     };
-    ($t:expr, $s:expr) => {
-        Ok(($t, Span { start: $s, end: $s }))
+    ($l:expr, $t:expr, $s:expr) => {
+        Ok((
+            $t,
+            Span {
+                start: $s,
+                end: $s,
+                end_offset: $l.end_offset(),
+            },
+        ))
     };
 }
 
@@ -140,88 +168,262 @@ macro_rules! match_escape_unicode_suffix {
     };
 }
 
-macro_rules! match_quote {
-    () => {
-        '\"'
-    };
-}
-
 macro_rules! match_newline {
     () => {
         '\n'
     };
 }
 
+/// Check whether a character may start an identifier
+#[inline]
+fn is_identifier_start(ch: char) -> bool {
+    ch.is_alphabetic() || ch == '$' || ch == '_'
+}
+
+/// Check whether a character may appear within an identifier
+#[inline]
+fn is_identifier_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '$' || ch == '_'
+}
+
 pub struct Lexer<'a> {
-    /// An iterator producing `char` values
-    chars: &'a mut dyn Iterator<Item = char>,
+    /// A [Decoder] producing `char` values
+    chars: Box<dyn Decoder + 'a>,
 
     /// Lookahead buffer
     buffer: Vec<char>,
 
-    /// Optional pushback character
-    pushback: Option<char>,
+    /// Optional pushback character, along with its [Coords]
+    pushback: Option<(char, Coords)>,
 
     /// Current input [Coords]
     coords: Coords,
+
+    /// The [Coords] prior to the last character read, restored when the character is pushed back
+    previous: Coords,
+
+    /// The last character read, used to detect line breaks
+    last: Option<char>,
+
+    /// The [Limits] to enforce whilst lexing
+    limits: Limits,
+
+    /// The [NumericMode] used to convert numeric representations
+    numeric_mode: NumericMode,
+
+    /// The [Coords] of the first replacement made by a lossy decoder
+    first_replacement: Option<Coords>,
+
+    /// The [CommentPolicy] applied to comments within the input
+    comments: CommentPolicy,
+
+    /// The [Extensions] to strict JSON which are enabled
+    extensions: Extensions,
+
+    /// Comments skipped over since the last call to [Lexer::take_comments], along with their
+    /// [Span]s. Only populated when using [CommentPolicy::Report]
+    pending_comments: Vec<(String, Span)>,
+
+    /// Whether record separators are recognised, as when reading a JSON text sequence
+    record_separators: bool,
+
+    /// Whether the token being matched is in the position of an object key, (see
+    /// [Lexer::consume_key])
+    key_position: bool,
 }
 
 impl<'a> Lexer<'a> {
     pub fn new(chars: &'a mut impl Iterator<Item = char>) -> Self {
+        Self::with_options(chars, &ParserOptions::default())
+    }
+
+    /// Create a new lexer configured using a given set of [ParserOptions]
+    pub fn with_options(
+        chars: &'a mut impl Iterator<Item = char>,
+        options: &ParserOptions,
+    ) -> Self {
+        Self::with_decoder(Box::new(Chars::new(chars)), options)
+    }
+
+    /// Create a new lexer which pulls `char`s from a given [Decoder]. Any error raised by the
+    /// decoder is surfaced by the lexer at the point at which decoding failed
+    pub fn with_decoder(chars: Box<dyn Decoder + 'a>, options: &ParserOptions) -> Self {
         Lexer {
             chars,
             buffer: Vec::with_capacity(DEFAULT_BUFFER_SIZE),
             pushback: None,
             coords: Coords::default(),
+            previous: Coords::default(),
+            last: None,
+            limits: options.limits,
+            numeric_mode: options.numeric_mode,
+            first_replacement: None,
+            comments: match options.comments {
+                CommentPolicy::Reject if options.dialect == Dialect::Json5 => CommentPolicy::Skip,
+                policy => policy,
+            },
+            extensions: options.extensions(),
+            pending_comments: vec![],
+            record_separators: false,
+            key_position: false,
         }
     }
 
+    /// Recognise RFC 7464 record separators, which are produced as [Token::RecordSeparator]s
+    /// between tokens. A record separator found part way through a token raises a
+    /// [ParserErrorDetails::TruncatedRecord] error, and is left to be consumed as the next token
+    pub(crate) fn with_record_separators(mut self) -> Self {
+        self.record_separators = true;
+        self
+    }
+
+    /// Get the current input [Coords]
+    pub fn coords(&self) -> Coords {
+        self.coords
+    }
+
+    /// Skip forward to the next comma or closing bracket at the current level of nesting, so that
+    /// parsing may resume after an error. The [Token] which triggered the error is taken into
+    /// account (along with a delimiter swallowed whilst matching a literal such as `nul,`), so a
+    /// comma or closing bracket which has already been consumed is returned immediately, and an
+    /// opening bracket causes the nested structure to be skipped. Any errors raised whilst
+    /// skipping are ignored, unless the end of the input is reached
+    pub(crate) fn resync(&mut self) -> ParserResult<PackedToken<'_>> {
+        let mut depth = match self.buffer.last() {
+            Some(',') => return packed_token!(self, Token::Comma, self.coords),
+            Some('}') => return packed_token!(self, Token::EndObject, self.coords),
+            Some(']') => return packed_token!(self, Token::EndArray, self.coords),
+            Some('{') | Some('[') => 1,
+            _ => 0,
+        };
+        loop {
+            match self.consume() {
+                Ok((Token::StartObject | Token::StartArray, _)) => depth += 1,
+                Ok((Token::EndObject | Token::EndArray, _)) if depth > 0 => depth -= 1,
+                Ok((token @ (Token::EndObject | Token::EndArray | Token::Comma), span))
+                    if depth == 0 =>
+                {
+                    return Ok((token, span))
+                }
+                Ok((Token::EndOfInput, _)) => {
+                    return lexer_error!(ParserErrorDetails::EndOfInput, self.coords)
+                }
+                Err(err) if !err.is_recoverable() => return Err(err),
+                _ => (),
+            }
+        }
+    }
+
+    /// Check whether the end of the input has been reached by the last call to [Lexer::consume]
+    pub(crate) fn at_end(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    /// Get a summary of any replacements made by a lossy decoder so far
+    pub fn replacements(&self) -> Option<Replacements> {
+        self.first_replacement.map(|first| Replacements {
+            count: self.chars.replacements(),
+            first,
+        })
+    }
+
+    /// Get the [Encoding] of the bytes being decoded, (after any automatic detection). `None` if
+    /// the lexer is working from a stream of `char`s
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.chars.source_encoding()
+    }
+
+    /// Take any comments skipped over since the last call, along with their [Span]s. Comments are
+    /// only retained when using [CommentPolicy::Report]
+    pub(crate) fn take_comments(&mut self) -> Vec<(String, Span)> {
+        std::mem::take(&mut self.pending_comments)
+    }
+
     /// Reset the current state
     fn reset(&mut self) {
         self.buffer.clear();
     }
 
+    /// Consume the next [Token] from the input, given that it's in the position of an object key
+    /// (or the comma or closing brace which may stand in its place). Any word found is then
+    /// matched as an unquoted key, (including reserved words such as `null`), or raises an error
+    /// naming [Extension::UnquotedKeys] if unquoted keys haven't been enabled
+    pub fn consume_key(&mut self) -> ParserResult<PackedToken<'_>> {
+        self.key_position = true;
+        let token = self.consume();
+        self.key_position = false;
+        token
+    }
+
     /// Consume the next [Token] from the input
-    pub fn consume(&mut self) -> ParserResult<PackedToken> {
+    pub fn consume(&mut self) -> ParserResult<PackedToken<'_>> {
         self.reset();
         match self.advance(true) {
             Ok(_) => match self.buffer[0] {
-                '{' => packed_token!(Token::StartObject, self.coords),
-                '}' => packed_token!(Token::EndObject, self.coords),
-                '[' => packed_token!(Token::StartArray, self.coords),
-                ']' => packed_token!(Token::EndArray, self.coords),
-                ':' => packed_token!(Token::Colon, self.coords),
-                ',' => packed_token!(Token::Comma, self.coords),
-                '\"' => self.match_string(),
+                '{' => packed_token!(self, Token::StartObject, self.coords),
+                '}' => packed_token!(self, Token::EndObject, self.coords),
+                '[' => packed_token!(self, Token::StartArray, self.coords),
+                ']' => packed_token!(self, Token::EndArray, self.coords),
+                ':' => packed_token!(self, Token::Colon, self.coords),
+                ',' => packed_token!(self, Token::Comma, self.coords),
+                RECORD_SEPARATOR if self.record_separators => {
+                    packed_token!(self, Token::RecordSeparator, self.coords)
+                }
+                '\"' => self.match_string('\"'),
+                '\'' => {
+                    self.require(Extension::SingleQuotedStrings, self.coords)?;
+                    self.match_string('\'')
+                }
+                ch if is_identifier_start(ch)
+                    && (self.key_position || self.extensions.allows(Extension::UnquotedKeys)) =>
+                {
+                    self.match_word()
+                }
                 'n' => self.match_null(),
                 't' => self.match_true(),
                 'f' => self.match_false(),
+                '-' | '+' | '.' | '0'..='9' if self.extensions.allows_extended_numbers() => {
+                    self.match_extended_number()
+                }
                 '-' => self.match_number(),
                 d if d.is_ascii_digit() => self.match_number(),
+                '+' => lexer_error!(
+                    ParserErrorDetails::ExtensionRequired(Extension::PlusSign),
+                    self.coords
+                ),
+                '.' => lexer_error!(
+                    ParserErrorDetails::ExtensionRequired(Extension::LeadingDecimalPoint),
+                    self.coords
+                ),
+                ch if is_identifier_start(ch) => self.match_word(),
                 ch => lexer_error!(ParserErrorDetails::InvalidCharacter(ch), self.coords),
             },
             Err(err) => match err.details {
                 ParserErrorDetails::EndOfInput => {
-                    packed_token!(Token::EndOfInput, self.coords)
+                    packed_token!(self, Token::EndOfInput, self.coords)
                 }
-                _ => match err.coords {
-                    Some(coords) => lexer_error!(err.details, coords),
-                    None => lexer_error!(err.details),
-                },
+                _ => Err(err),
             },
         }
     }
 
-    /// Match on a valid Json string.
-    fn match_string(&mut self) -> ParserResult<PackedToken> {
+    /// Match on a valid Json string, enclosed in a given quote character
+    fn match_string(&mut self, quote: char) -> ParserResult<PackedToken<'_>> {
         let mut adjusted_coords = self.coords;
         loop {
+            if let Some(max) = self.limits.max_string_length {
+                if self.buffer.len() - 1 > max {
+                    return lexer_error!(ParserErrorDetails::StringTooLong(max), self.coords);
+                }
+            }
             match self.advance(false) {
-                Ok(_) => match self.buffer.last().unwrap() {
+                Ok(_) => match *self.buffer.last().unwrap() {
                     match_escape!() => match self.advance(false) {
-                        Ok(_) => match self.buffer.last().unwrap() {
+                        Ok(_) => match *self.buffer.last().unwrap() {
                             match_escape_non_unicode_suffix!() => (),
                             match_escape_unicode_suffix!() => self.check_unicode_sequence()?,
+                            '\'' if self.extensions.allows(Extension::SingleQuotedStrings) => (),
+                            '\n' | '\r' => self.match_line_continuation()?,
                             _ => {
                                 adjusted_coords.inc_n(2);
                                 return lexer_error!(
@@ -232,22 +434,69 @@ impl<'a> Lexer<'a> {
                                 );
                             }
                         },
-                        Err(err) => {
-                            return lexer_error!(err.details, err.coords.unwrap());
-                        }
+                        Err(err) => return Err(err),
                     },
-                    match_quote!() => {
+                    ch if ch == quote => {
                         return packed_token!(
-                            Token::Str(self.buffer_to_string()),
+                            self,
+                            Token::Str(self.quoted_string(quote)),
                             adjusted_coords,
                             self.coords
                         );
                     }
                     _ => (),
                 },
-                Err(err) => return lexer_error!(err.details, err.coords.unwrap()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Match on an escaped line break within a string, which is removed from the string along with
+    /// its escape. A `\r\n` pair is treated as a single line break
+    fn match_line_continuation(&mut self) -> ParserResult<()> {
+        self.require(Extension::MultiLineStrings, self.previous)?;
+        let line_break = self.buffer.pop();
+        self.buffer.pop();
+        if line_break == Some('\r') {
+            match self.next_in_token()? {
+                Some('\n') => {
+                    self.buffer.pop();
+                }
+                Some(_) => self.pushback(),
+                None => (),
             }
         }
+        Ok(())
+    }
+
+    /// Convert the contents of the buffer into a string enclosed in double quotes. Single-quoted
+    /// strings and `\'` escapes are normalised, so that strings take the same form regardless of
+    /// the [Dialect] in use
+    fn quoted_string(&self, quote: char) -> String {
+        if quote == '\"' && !self.buffer.contains(&'\'') {
+            return self.buffer_to_string();
+        }
+        let mut s = String::with_capacity(self.buffer.len() + 2);
+        let mut escaped = false;
+        s.push('\"');
+        for &ch in &self.buffer[1..self.buffer.len() - 1] {
+            if escaped {
+                if ch != '\'' {
+                    s.push('\\');
+                }
+                s.push(ch);
+                escaped = false;
+            } else if ch == '\\' {
+                escaped = true;
+            } else {
+                if ch == '\"' {
+                    s.push('\\');
+                }
+                s.push(ch);
+            }
+        }
+        s.push('\"');
+        s
     }
 
     #[inline]
@@ -278,7 +527,7 @@ impl<'a> Lexer<'a> {
     /// - Exponents must be well-formed
     /// - An non-exponent alphabetic found in the representation will result in an error
     /// - Numbers can be terminated by commas, brackets and whitespace only (end of pair, end of array)
-    fn match_number(&mut self) -> ParserResult<PackedToken> {
+    fn match_number(&mut self) -> ParserResult<PackedToken<'_>> {
         let mut adjusted_coords = self.coords;
         let mut have_exponent = false;
         let mut have_decimal = false;
@@ -287,6 +536,14 @@ impl<'a> Lexer<'a> {
             Ok(integral) => {
                 have_decimal = !integral;
                 loop {
+                    if let Some(max) = self.limits.max_number_length {
+                        if self.buffer.len() > max {
+                            return lexer_error!(
+                                ParserErrorDetails::NumberTooLong(max),
+                                self.coords
+                            );
+                        }
+                    }
                     match self.advance(false) {
                         Ok(_) => match self.buffer.last().unwrap() {
                             match_digit!() => (),
@@ -318,17 +575,30 @@ impl<'a> Lexer<'a> {
                                 }
                             }
                             match_numeric_terminator!() => {
-                                self.pushback(false);
+                                self.pushback();
                                 break;
                             }
                             match_newline!() => {
-                                self.pushback(true);
+                                self.pushback();
                                 break;
                             }
                             ch if ch.is_ascii_whitespace() => {
-                                self.pushback(false);
+                                self.pushback();
                                 break;
                             }
+                            '/' if self.comments != CommentPolicy::Reject => {
+                                self.pushback();
+                                break;
+                            }
+                            'x' | 'X'
+                                if self.buffer[..] == ['0', 'x']
+                                    || self.buffer[..] == ['0', 'X'] =>
+                            {
+                                return lexer_error!(
+                                    ParserErrorDetails::ExtensionRequired(Extension::HexNumbers),
+                                    adjusted_coords
+                                );
+                            }
                             ch if ch.is_alphabetic() => {
                                 return lexer_error!(
                                     ParserErrorDetails::InvalidNumericRepresentation(
@@ -346,32 +616,263 @@ impl<'a> Lexer<'a> {
                                 );
                             }
                         },
-                        Err(err) => match err.coords {
-                            Some(coords) => return lexer_error!(err.details, coords),
-                            None => return lexer_error!(err.details),
-                        },
+                        Err(err) if err.details == ParserErrorDetails::EndOfInput => break,
+                        Err(err) => return Err(err),
                     }
                 }
             }
-            Err(err) => match err.coords {
-                Some(coords) => return lexer_error!(err.details, coords),
-                None => return lexer_error!(err.details),
-            },
+            Err(err) => return Err(err),
         }
 
+        if self.buffer.last() == Some(&'.') {
+            return lexer_error!(
+                ParserErrorDetails::ExtensionRequired(Extension::TrailingDecimalPoint),
+                adjusted_coords
+            );
+        }
         self.parse_numeric(!have_decimal, adjusted_coords, self.coords)
     }
 
+    /// Match on a number when any of the number-related [Extension]s are enabled. Each extension
+    /// is checked as it's found, so that any error raised names the extension required
+    fn match_extended_number(&mut self) -> ParserResult<PackedToken<'_>> {
+        let start = self.coords;
+        let mut ch = Some(self.buffer[0]);
+        if let Some(sign @ ('+' | '-')) = ch {
+            if sign == '+' {
+                self.require(Extension::PlusSign, start)?;
+            }
+            ch = self.next_in_token()?;
+        }
+        if let Some('I' | 'N') = ch {
+            while let Some(ch) = self.next_in_token()? {
+                if !ch.is_alphanumeric() {
+                    self.pushback();
+                    break;
+                }
+            }
+            let signed = matches!(self.buffer[0], '+' | '-');
+            let word: String = self.buffer[signed as usize..].iter().collect();
+            return match word.as_str() {
+                "Infinity" | "NaN" => self.match_non_finite(start),
+                _ => lexer_error!(
+                    ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                    start
+                ),
+            };
+        }
+        let mut integral_digits = 0;
+        if ch == Some('0') {
+            ch = self.next_in_token()?;
+            match ch {
+                Some('x' | 'X') => {
+                    self.require(Extension::HexNumbers, start)?;
+                    return self.match_hex_digits(start);
+                }
+                Some(d) if d.is_ascii_digit() => {
+                    return lexer_error!(
+                        ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                        start
+                    )
+                }
+                _ => integral_digits = 1,
+            }
+        }
+        let mut fraction_digits: Option<usize> = None;
+        let mut exponent_digits: Option<usize> = None;
+        loop {
+            if let Some(max) = self.limits.max_number_length {
+                if self.buffer.len() > max {
+                    return lexer_error!(ParserErrorDetails::NumberTooLong(max), self.coords);
+                }
+            }
+            match ch {
+                Some(d) if d.is_ascii_digit() => {
+                    match (exponent_digits.as_mut(), fraction_digits.as_mut()) {
+                        (Some(n), _) | (None, Some(n)) => *n += 1,
+                        (None, None) => integral_digits += 1,
+                    }
+                }
+                Some('.') if fraction_digits.is_none() && exponent_digits.is_none() => {
+                    fraction_digits = Some(0)
+                }
+                Some('e' | 'E') if exponent_digits.is_none() => {
+                    exponent_digits = Some(0);
+                    ch = self.next_in_token()?;
+                    if let Some('+' | '-') = ch {
+                        ch = self.next_in_token()?;
+                    }
+                    continue;
+                }
+                Some(c) if c.is_alphanumeric() || c == '.' => {
+                    return lexer_error!(
+                        ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                        self.coords
+                    )
+                }
+                Some(_) => {
+                    self.pushback();
+                    break;
+                }
+                None => break,
+            }
+            ch = self.next_in_token()?;
+        }
+        if integral_digits + fraction_digits.unwrap_or(0) == 0 || exponent_digits == Some(0) {
+            return lexer_error!(
+                ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                start
+            );
+        }
+        if integral_digits == 0 {
+            self.require(Extension::LeadingDecimalPoint, start)?;
+        }
+        if fraction_digits == Some(0) {
+            self.require(Extension::TrailingDecimalPoint, start)?;
+        }
+        if self.numeric_mode == NumericMode::Raw {
+            return packed_token!(
+                self,
+                Token::RawNumber(self.buffer_to_string()),
+                start,
+                self.coords
+            );
+        }
+        if self.buffer[0] == '+' {
+            self.buffer.remove(0);
+        }
+        let integral = fraction_digits.is_none() && exponent_digits.is_none();
+        self.parse_numeric(integral, start, self.coords)
+    }
+
+    /// Match on the digits of a hexadecimal number, following the `0x` prefix
+    fn match_hex_digits(&mut self, start: Coords) -> ParserResult<PackedToken<'_>> {
+        let prefix = self.buffer.len();
+        while let Some(ch) = self.next_in_token()? {
+            if !ch.is_ascii_hexdigit() {
+                self.pushback();
+                if ch.is_alphanumeric() {
+                    return lexer_error!(
+                        ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                        self.coords
+                    );
+                }
+                break;
+            }
+        }
+        let digits: String = self.buffer[prefix..].iter().collect();
+        let value = match i64::from_str_radix(&digits, 16) {
+            Ok(value) if self.buffer[0] == '-' => -value,
+            Ok(value) => value,
+            Err(_) => {
+                return lexer_error!(
+                    ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
+                    start
+                )
+            }
+        };
+        match self.numeric_mode {
+            NumericMode::Raw => {
+                packed_token!(
+                    self,
+                    Token::RawNumber(self.buffer_to_string()),
+                    start,
+                    self.coords
+                )
+            }
+            NumericMode::Mixed => packed_token!(self, Token::Integer(value), start, self.coords),
+            NumericMode::Float => {
+                packed_token!(self, Token::Float(value as f64), start, self.coords)
+            }
+        }
+    }
+
+    /// Produce a token for a (possibly signed) `NaN` or `Infinity` literal held in the buffer
+    fn match_non_finite(&mut self, start: Coords) -> ParserResult<PackedToken<'_>> {
+        self.require(Extension::NonFiniteNumbers, start)?;
+        if self.numeric_mode == NumericMode::Raw {
+            return packed_token!(
+                self,
+                Token::RawNumber(self.buffer_to_string()),
+                start,
+                self.coords
+            );
+        }
+        let value = match (self.buffer[0], self.buffer.last()) {
+            (_, Some('N')) => f64::NAN,
+            ('-', _) => f64::NEG_INFINITY,
+            _ => f64::INFINITY,
+        };
+        packed_token!(self, Token::Float(value), start, self.coords)
+    }
+
+    /// Match on a word made up of identifier characters. Depending on the [Extension]s enabled, this
+    /// may be a literal, a non-finite number or an unquoted key. In the position of a key, every
+    /// word (reserved or not) is an unquoted key
+    fn match_word(&mut self) -> ParserResult<PackedToken<'_>> {
+        let start = self.coords;
+        while let Some(ch) = self.next_in_token()? {
+            if !is_identifier_char(ch) {
+                self.pushback();
+                break;
+            }
+        }
+        let word = self.buffer_to_string();
+        let unquoted_keys = self.extensions.allows(Extension::UnquotedKeys);
+        match word.as_str() {
+            _ if self.key_position && unquoted_keys => packed_token!(
+                self,
+                Token::Identifier(format!("\"{}\"", word)),
+                start,
+                self.coords
+            ),
+            _ if self.key_position => lexer_error!(
+                ParserErrorDetails::ExtensionRequired(Extension::UnquotedKeys),
+                start
+            ),
+            "null" => packed_token!(self, Token::Null, start, self.coords),
+            "true" => packed_token!(self, Token::Boolean(true), start, self.coords),
+            "false" => packed_token!(self, Token::Boolean(false), start, self.coords),
+            "Infinity" | "NaN" => self.match_non_finite(start),
+            _ if unquoted_keys => packed_token!(
+                self,
+                Token::Identifier(format!("\"{}\"", word)),
+                start,
+                self.coords
+            ),
+            _ => lexer_error!(ParserErrorDetails::InvalidCharacter(self.buffer[0]), start),
+        }
+    }
+
+    /// Check that a given [Extension] is enabled, raising an error at some [Coords] if not
+    #[inline]
+    fn require(&self, extension: Extension, coords: Coords) -> ParserResult<()> {
+        if self.extensions.allows(extension) {
+            Ok(())
+        } else {
+            lexer_error!(ParserErrorDetails::ExtensionRequired(extension), coords)
+        }
+    }
+
+    /// Advance a character within the current token, returning `None` at the end of the input
+    #[inline]
+    fn next_in_token(&mut self) -> ParserResult<Option<char>> {
+        match self.advance(false) {
+            Ok(_) => Ok(self.buffer.last().copied()),
+            Err(err) if err.details == ParserErrorDetails::EndOfInput => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
     fn check_following_exponent(&mut self) -> ParserResult<()> {
-        self.advance(false).and_then(|_| {
-            return match self.buffer.last().unwrap() {
+        self.advance(false)
+            .and_then(|_| match self.buffer.last().unwrap() {
                 match_plus_minus!() => Ok(()),
                 _ => lexer_error!(
                     ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
                     self.coords
                 ),
-            };
-        })
+            })
     }
 
     /// Convert the contents of the buffer into an owned [String]
@@ -387,41 +888,44 @@ impl<'a> Lexer<'a> {
         self.buffer.iter().map(|ch| *ch as u8).collect()
     }
 
-    #[cfg(not(feature = "mixed_numerics"))]
+    /// Convert the contents of the buffer into a numeric [Token], based on the current
+    /// [NumericMode]. In mixed mode, integral values which can't be represented as an `i64` fall
+    /// back to being parsed as floats
     #[inline]
     fn parse_numeric(
         &mut self,
         integral: bool,
         start_coords: Coords,
         end_coords: Coords,
-    ) -> ParserResult<PackedToken> {
-        packed_token!(
-            Token::Float(fast_float::parse(self.buffer_to_bytes_unchecked()).unwrap()),
-            start_coords,
-            end_coords
-        )
-    }
-
-    #[cfg(feature = "mixed_numerics")]
-    #[inline]
-    fn parse_numeric(
-        &mut self,
-        integral: bool,
-        start_coords: Coords,
-        end_coords: Coords,
-    ) -> ParserResult<PackedToken> {
-        if integral {
-            packed_token!(
-                Token::Integer(lexical::parse(self.buffer_to_bytes_unchecked()).unwrap()),
-                start_coords,
-                end_coords
-            )
-        } else {
-            packed_token!(
+    ) -> ParserResult<PackedToken<'_>> {
+        match self.numeric_mode {
+            NumericMode::Raw => {
+                packed_token!(
+                    self,
+                    Token::RawNumber(self.buffer_to_string()),
+                    start_coords,
+                    end_coords
+                )
+            }
+            NumericMode::Mixed if integral => {
+                match lexical::parse::<i64, _>(self.buffer_to_bytes_unchecked()) {
+                    Ok(value) => {
+                        packed_token!(self, Token::Integer(value), start_coords, end_coords)
+                    }
+                    Err(_) => packed_token!(
+                        self,
+                        Token::Float(fast_float::parse(self.buffer_to_bytes_unchecked()).unwrap()),
+                        start_coords,
+                        end_coords
+                    ),
+                }
+            }
+            _ => packed_token!(
+                self,
                 Token::Float(fast_float::parse(self.buffer_to_bytes_unchecked()).unwrap()),
                 start_coords,
                 end_coords
-            )
+            ),
         }
     }
 
@@ -438,9 +942,11 @@ impl<'a> Lexer<'a> {
             match_minus!() => self
                 .advance(false)
                 .and_then(|_| self.check_following_minus()),
-            match_zero!() => self
-                .advance(false)
-                .and_then(|_| self.check_following_zero()),
+            match_zero!() => match self.advance(false) {
+                Ok(_) => self.check_following_zero(),
+                Err(err) if err.details == ParserErrorDetails::EndOfInput => Ok(true),
+                Err(err) => Err(err),
+            },
             _ => Ok(true),
         }
     }
@@ -454,11 +960,11 @@ impl<'a> Lexer<'a> {
                 self.coords
             ),
             match_newline!() => {
-                self.pushback(true);
+                self.pushback();
                 Ok(true)
             }
             _ => {
-                self.pushback(false);
+                self.pushback();
                 Ok(true)
             }
         }
@@ -478,9 +984,13 @@ impl<'a> Lexer<'a> {
                 Ok(false)
             }),
             match_newline!() => {
-                self.pushback(true);
+                self.pushback();
                 Ok(true)
             }
+            'I' => lexer_error!(
+                ParserErrorDetails::ExtensionRequired(Extension::NonFiniteNumbers),
+                self.coords
+            ),
             _ => lexer_error!(
                 ParserErrorDetails::InvalidNumericRepresentation(self.buffer_to_string()),
                 self.coords
@@ -489,11 +999,11 @@ impl<'a> Lexer<'a> {
     }
 
     /// Match on a null token
-    fn match_null(&mut self) -> ParserResult<PackedToken> {
+    fn match_null(&mut self) -> ParserResult<PackedToken<'_>> {
         let start_coords = self.coords;
         self.advance_n(3, false).and_then(|_| {
             if self.buffer[0..=3] == NULL_PATTERN {
-                packed_token!(Token::Null, start_coords, self.coords)
+                packed_token!(self, Token::Null, start_coords, self.coords)
             } else {
                 lexer_error!(
                     ParserErrorDetails::MatchFailed(
@@ -507,11 +1017,11 @@ impl<'a> Lexer<'a> {
     }
 
     /// Match on a true token
-    fn match_true(&mut self) -> ParserResult<PackedToken> {
+    fn match_true(&mut self) -> ParserResult<PackedToken<'_>> {
         let start_coords = self.coords;
         self.advance_n(3, false).and_then(|_| {
             if self.buffer[0..=3] == TRUE_PATTERN {
-                packed_token!(Token::Boolean(true), start_coords, self.coords)
+                packed_token!(self, Token::Boolean(true), start_coords, self.coords)
             } else {
                 lexer_error!(
                     ParserErrorDetails::MatchFailed(
@@ -525,11 +1035,11 @@ impl<'a> Lexer<'a> {
     }
 
     /// Match on a false token
-    fn match_false(&mut self) -> ParserResult<PackedToken> {
+    fn match_false(&mut self) -> ParserResult<PackedToken<'_>> {
         let start_coords = self.coords;
         self.advance_n(4, false).and_then(|_| {
             if self.buffer[0..=4] == FALSE_PATTERN {
-                packed_token!(Token::Boolean(false), start_coords, self.coords)
+                packed_token!(self, Token::Boolean(false), start_coords, self.coords)
             } else {
                 lexer_error!(
                     ParserErrorDetails::MatchFailed(
@@ -542,33 +1052,54 @@ impl<'a> Lexer<'a> {
         })
     }
 
-    /// Get the next character from either the pushback or from the decoder
+    /// Get the next character (along with its [Coords]) from either the pushback or from the
+    /// decoder
     #[inline]
-    fn next_char(&mut self) -> ParserResult<char> {
-        match self.pushback {
+    fn next_char(&mut self) -> ParserResult<(char, Coords)> {
+        if let Some(pushed) = self.pushback.take() {
+            self.last = Some(pushed.0);
+            return Ok(pushed);
+        }
+        let mut coords = self.coords;
+        let offset = self.chars.offset();
+        match self.chars.next_char() {
             Some(c) => {
-                self.pushback = None;
-                Ok(c)
+                coords.inc_char(c, offset, self.last);
+                self.last = Some(c);
+                if c == char::REPLACEMENT_CHARACTER
+                    && self.first_replacement.is_none()
+                    && self.chars.replacements() > 0
+                {
+                    self.first_replacement = Some(coords);
+                }
+                Ok((c, coords))
             }
-            None => match self.chars.next() {
-                Some(c) => Ok(c),
+            None => match self.chars.take_error() {
+                Some(mut err) => {
+                    err.coords = Some(self.coords);
+                    Err(err)
+                }
                 None => lexer_error!(ParserErrorDetails::EndOfInput),
             },
         }
     }
 
+    /// Get the byte offset immediately following the last character read, (discounting any
+    /// character which has been pushed back)
+    #[inline]
+    fn end_offset(&self) -> usize {
+        match self.pushback {
+            Some((_, coords)) => coords.offset,
+            None => self.chars.offset(),
+        }
+    }
+
     /// Transfer the last character in the buffer to the pushback
     #[inline]
-    fn pushback(&mut self, newline: bool) {
-        if !self.buffer.is_empty() {
-            self.pushback = self.buffer.pop();
-            self.coords.absolute -= 1;
-            self.coords.column -= 1;
-            if newline {
-                self.coords.line -= 1;
-            }
-        } else {
-            self.pushback = None;
+    fn pushback(&mut self) {
+        self.pushback = self.buffer.pop().map(|c| (c, self.coords));
+        if self.pushback.is_some() {
+            self.coords = self.previous;
         }
     }
 
@@ -582,33 +1113,96 @@ impl<'a> Lexer<'a> {
     }
 
     /// Advance a character in the input stream, and push onto the end of the internal buffer. This
-    /// will update the current input [Coords]. Optionally skip whitespace in the input, (but still
-    /// update the coordinates accordingly).
+    /// will update the current input [Coords]. Optionally skip whitespace (along with any comments,
+    /// if allowed) in the input, (but still update the coordinates accordingly).
     fn advance(&mut self, skip_whitespace: bool) -> ParserResult<()> {
         loop {
-            match self.next_char() {
+            let c = self.read_char()?;
+            if !skip_whitespace {
+                self.buffer.push(c);
+                if c == RECORD_SEPARATOR && self.record_separators {
+                    let coords = self.coords;
+                    self.pushback();
+                    return lexer_error!(ParserErrorDetails::TruncatedRecord, coords);
+                }
+                break;
+            }
+            if c == '/' && self.comments != CommentPolicy::Reject {
+                self.skip_comment()?;
+            } else if !c.is_ascii_whitespace() {
+                self.buffer.push(c);
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next character from the input, updating the current input [Coords]
+    fn read_char(&mut self) -> ParserResult<char> {
+        match self.next_char() {
+            Ok((c, coords)) => {
+                self.previous = self.coords;
+                self.coords = coords;
+                if let Some(max) = self.limits.max_input_length {
+                    if self.chars.offset() > max {
+                        return lexer_error!(ParserErrorDetails::InputTooLarge(max), self.coords);
+                    }
+                }
+                Ok(c)
+            }
+            Err(err) => match err.details {
+                ParserErrorDetails::EndOfInput => {
+                    lexer_error!(ParserErrorDetails::EndOfInput, self.coords)
+                }
+                _ => Err(err),
+            },
+        }
+    }
+
+    /// Skip over a `//` line comment or `/* */` block comment, given that the opening `/` has just
+    /// been read. A line comment runs up to (but doesn't include) the next line break, or the end
+    /// of the input
+    fn skip_comment(&mut self) -> ParserResult<()> {
+        let start = self.coords;
+        let mut text = String::from('/');
+        let block = match self.read_char() {
+            Ok('/') => false,
+            Ok('*') => true,
+            _ => return lexer_error!(ParserErrorDetails::InvalidCharacter('/'), start),
+        };
+        text.push(if block { '*' } else { '/' });
+        let mut end = self.coords;
+        let mut end_offset = self.end_offset();
+        loop {
+            match self.read_char() {
+                Ok('\n' | '\r') if !block => break,
                 Ok(c) => {
-                    self.coords.inc(c == '\n' || c == '\r');
-                    if skip_whitespace {
-                        if !c.is_ascii_whitespace() {
-                            self.buffer.push(c);
-                            break;
-                        }
-                    } else {
-                        self.buffer.push(c);
+                    text.push(c);
+                    end = self.coords;
+                    end_offset = self.end_offset();
+                    if block && c == '/' && text.len() > 3 && text.ends_with("*/") {
                         break;
                     }
                 }
-                Err(err) => {
-                    return match err.details {
-                        ParserErrorDetails::EndOfInput => {
-                            lexer_error!(ParserErrorDetails::EndOfInput, self.coords)
-                        }
-                        _ => lexer_error!(err.details),
-                    };
-                }
+                Err(err) => match err.details {
+                    ParserErrorDetails::EndOfInput if !block => break,
+                    ParserErrorDetails::EndOfInput => {
+                        return lexer_error!(ParserErrorDetails::UnterminatedComment, start)
+                    }
+                    _ => return Err(err),
+                },
             }
         }
+        if self.comments == CommentPolicy::Report {
+            self.pending_comments.push((
+                text,
+                Span {
+                    start,
+                    end,
+                    end_offset,
+                },
+            ));
+        }
         Ok(())
     }
 }
@@ -616,8 +1210,11 @@ impl<'a> Lexer<'a> {
 #[cfg(test)]
 mod tests {
     use crate::coords::{Coords, Span};
+    use crate::decoders::{DecoderSelector, Encoding};
+    use crate::errors::ParserErrorDetails;
     use crate::errors::{ParserError, ParserResult};
     use crate::lexer::{Lexer, PackedToken, Token};
+    use crate::options::{CommentPolicy, Dialect, Extension, Limits, NumericMode, ParserOptions};
     use crate::{lines_from_relative_file, reader_from_bytes};
     use chisel_decoders::utf8::Utf8Decoder;
     use std::cell::RefCell;
@@ -791,7 +1388,7 @@ mod tests {
         let mut reader = reader_from_bytes!("true farse");
         let mut decoder = Utf8Decoder::new(&mut reader);
         let mut lexer = Lexer::new(&mut decoder);
-        let mut results: Vec<ParserResult<PackedToken>> = vec![];
+        let mut results: Vec<ParserResult<PackedToken<'_>>> = vec![];
         for _ in 1..=2 {
             results.push(lexer.consume());
         }
@@ -799,4 +1396,312 @@ mod tests {
         assert!(results[1].is_err());
         println!("Parse error: {:?}", results[1]);
     }
+
+    #[test]
+    fn should_enforce_string_and_number_length_limits() {
+        let options = ParserOptions::default().with_limits(Limits {
+            max_string_length: Some(3),
+            max_number_length: Some(4),
+            ..Default::default()
+        });
+        let mut reader = reader_from_bytes!("\"abc\" 1234 \"abcd\" 12345");
+        let mut decoder = Utf8Decoder::new(&mut reader);
+        let mut lexer = Lexer::with_options(&mut decoder, &options);
+        assert_eq!(
+            lexer.consume().unwrap().0,
+            Token::Str("\"abc\"".to_string())
+        );
+        assert!(lexer.consume().is_ok());
+        assert_eq!(
+            lexer.consume().err().unwrap().details,
+            ParserErrorDetails::StringTooLong(3)
+        );
+        let mut reader = reader_from_bytes!("12345");
+        let mut decoder = Utf8Decoder::new(&mut reader);
+        let mut lexer = Lexer::with_options(&mut decoder, &options);
+        assert_eq!(
+            lexer.consume().err().unwrap().details,
+            ParserErrorDetails::NumberTooLong(4)
+        );
+    }
+
+    #[test]
+    fn should_enforce_input_length_limit() {
+        let options = ParserOptions::default().with_limits(Limits {
+            max_input_length: Some(8),
+            ..Default::default()
+        });
+        let mut reader = reader_from_bytes!("[true,   false]");
+        let mut decoder = Utf8Decoder::new(&mut reader);
+        let mut lexer = Lexer::with_options(&mut decoder, &options);
+        let mut error = None;
+        while error.is_none() {
+            match lexer.consume() {
+                Ok((Token::EndOfInput, _)) => break,
+                Ok(_) => (),
+                Err(err) => error = Some(err),
+            }
+        }
+        let error = error.unwrap();
+        assert_eq!(error.details, ParserErrorDetails::InputTooLarge(8));
+        assert_eq!(error.coords.unwrap().absolute, 9);
+
+        let input = "[\"ééé\"]";
+        let mut chars = input.chars();
+        let mut lexer = Lexer::with_options(&mut chars, &options);
+        assert_eq!(lexer.consume().unwrap().0, Token::StartArray);
+        let error = lexer.consume().unwrap_err();
+        assert_eq!(error.details, ParserErrorDetails::InputTooLarge(8));
+        assert_eq!(error.coords.unwrap().absolute, 6);
+    }
+
+    #[test]
+    fn should_parse_numerics_according_to_mode() {
+        for (mode, expected) in [
+            (
+                NumericMode::Float,
+                [Token::Float(12.0), Token::Float(1.5), Token::Float(1e20)],
+            ),
+            (
+                NumericMode::Mixed,
+                [Token::Integer(12), Token::Float(1.5), Token::Float(1e20)],
+            ),
+            (
+                NumericMode::Raw,
+                [
+                    Token::RawNumber("12".to_string()),
+                    Token::RawNumber("1.5".to_string()),
+                    Token::RawNumber("100000000000000000000".to_string()),
+                ],
+            ),
+        ] {
+            let options = ParserOptions::default().with_numeric_mode(mode);
+            let mut reader = reader_from_bytes!("12 1.5 100000000000000000000,");
+            let mut decoder = Utf8Decoder::new(&mut reader);
+            let mut lexer = Lexer::with_options(&mut decoder, &options);
+            for token in expected {
+                assert_eq!(lexer.consume().unwrap().0, token);
+            }
+        }
+    }
+
+    #[test]
+    fn should_track_byte_offsets_and_utf16_columns() {
+        let input = "{\"naïve\" : \"𝄞\", \"b\" : 1}";
+        let mut chars = input.chars();
+        let mut lexer = Lexer::new(&mut chars);
+        let mut raw = vec![];
+        loop {
+            match lexer.consume().unwrap() {
+                (Token::EndOfInput, _) => break,
+                (_, span) => raw.push(&input[span.byte_range()]),
+            }
+        }
+        assert_eq!(
+            raw,
+            ["{", "\"naïve\"", ":", "\"𝄞\"", ",", "\"b\"", ":", "1", "}"]
+        );
+
+        let mut chars = input.chars();
+        let mut lexer = Lexer::new(&mut chars);
+        let spans: Vec<Span> = (0..5).map(|_| lexer.consume().unwrap().1).collect();
+        assert_eq!(spans[4].start.column, 15);
+        assert_eq!(spans[4].start.utf16_column, 16);
+        assert_eq!(spans[4].start.offset, 18);
+
+        let input = "[\"😀\", 😀]";
+        let mut chars = input.chars();
+        let mut lexer = Lexer::new(&mut chars);
+        let spans: Vec<Span> = (0..3).map(|_| lexer.consume().unwrap().1).collect();
+        assert_eq!(
+            (spans[1].start.utf16_column, spans[1].end.utf16_column),
+            (2, 5)
+        );
+        assert_eq!(spans[2].start.utf16_column, 6);
+        let err = lexer.consume().unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::InvalidCharacter('😀'));
+        assert_eq!(err.coords.unwrap().utf16_column, 8);
+    }
+
+    #[test]
+    fn should_track_byte_offsets_for_all_decoders() {
+        let selector = DecoderSelector::default();
+        for (bytes, encoding, offsets) in [
+            (
+                "[1, \"é\"]".as_bytes().to_vec(),
+                Encoding::Utf8,
+                [0, 1, 2, 4, 8],
+            ),
+            (
+                "[1, \"é\"]"
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes)
+                    .collect(),
+                Encoding::Utf16Le,
+                [0, 2, 4, 8, 14],
+            ),
+            (
+                [
+                    0xef, 0xbb, 0xbf, b'[', b'1', b',', b' ', b'"', 0xc3, 0xa9, b'"', b']',
+                ]
+                .to_vec(),
+                Encoding::Auto,
+                [3, 4, 5, 7, 11],
+            ),
+        ] {
+            let mut reader = BufReader::new(bytes.as_slice());
+            let mut lexer = Lexer::with_decoder(
+                selector.new_decoder(&mut reader, encoding),
+                &ParserOptions::default(),
+            );
+            let starts: Vec<usize> = (0..5)
+                .map(|_| lexer.consume().unwrap().1.start.offset)
+                .collect();
+            assert_eq!(starts, offsets, "{}", encoding);
+        }
+    }
+
+    #[test]
+    fn should_count_crlf_and_lone_cr_as_single_line_breaks() {
+        let mut chars = "[1,\r\n 2,\r3,\n\n4\r\n]".chars();
+        let mut lexer = Lexer::new(&mut chars);
+        let mut positions = vec![];
+        loop {
+            match lexer.consume().unwrap() {
+                (Token::EndOfInput, _) => break,
+                (_, span) => positions.push((span.start.line, span.start.column)),
+            }
+        }
+        assert_eq!(
+            positions,
+            [
+                (1, 1),
+                (1, 2),
+                (1, 3),
+                (2, 2),
+                (2, 3),
+                (3, 1),
+                (3, 2),
+                (5, 1),
+                (6, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_skip_comments_when_allowed() {
+        let input = "// lead\r\n[1/* a */,/**/2 // tail\n, /* multi\n * line */ true]// end";
+        let mut chars = input.chars();
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Report);
+        let mut lexer = Lexer::with_options(&mut chars, &options);
+        let mut positions = vec![];
+        loop {
+            match lexer.consume().unwrap() {
+                (Token::EndOfInput, _) => break,
+                (_, span) => positions.push((span.start.line, span.start.column)),
+            }
+        }
+        assert_eq!(
+            positions,
+            [(2, 1), (2, 2), (2, 10), (2, 15), (3, 1), (4, 12), (4, 16)]
+        );
+        let comments: Vec<(String, (usize, usize))> = lexer
+            .take_comments()
+            .into_iter()
+            .map(|(text, span)| (text, (span.end.line, span.end.column)))
+            .collect();
+        assert_eq!(comments[0], ("// lead".to_string(), (1, 7)));
+        assert_eq!(comments[3].0, "// tail");
+        assert_eq!(comments[4], ("/* multi\n * line */".to_string(), (4, 10)));
+        assert_eq!(comments[5].0, "// end");
+        assert!(lexer.take_comments().is_empty());
+    }
+
+    #[test]
+    fn should_reject_invalid_comments() {
+        let mut chars = "[1, // nope]".chars();
+        let mut lexer = Lexer::new(&mut chars);
+        lexer.consume().unwrap();
+        lexer.consume().unwrap();
+        lexer.consume().unwrap();
+        let err = lexer.consume().unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::InvalidCharacter('/'));
+        assert!(err.details.hint().unwrap().contains("parser's options"));
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Skip);
+        for (input, details, column) in [
+            ("[1 /* open", ParserErrorDetails::UnterminatedComment, 4),
+            ("[1 / 2]", ParserErrorDetails::InvalidCharacter('/'), 4),
+        ] {
+            let mut chars = input.chars();
+            let mut lexer = Lexer::with_options(&mut chars, &options);
+            lexer.consume().unwrap();
+            lexer.consume().unwrap();
+            let err = lexer.consume().unwrap_err();
+            assert_eq!(err.details, details);
+            assert_eq!(err.coords.unwrap().column, column);
+        }
+    }
+
+    #[test]
+    fn should_lex_json5_extensions_according_to_mode() {
+        let input = "0x1f .5 5. +1 -Infinity NaN key 'it\\'s' \"a\\\r\nb\"";
+        for (mode, numbers) in [
+            (
+                NumericMode::Mixed,
+                vec![
+                    Token::Integer(31),
+                    Token::Float(0.5),
+                    Token::Float(5.0),
+                    Token::Integer(1),
+                    Token::Float(f64::NEG_INFINITY),
+                ],
+            ),
+            (
+                NumericMode::Raw,
+                ["0x1f", ".5", "5.", "+1", "-Infinity", "NaN"]
+                    .iter()
+                    .map(|raw| Token::RawNumber(raw.to_string()))
+                    .collect(),
+            ),
+        ] {
+            let options = ParserOptions::default()
+                .with_dialect(Dialect::Json5)
+                .with_numeric_mode(mode);
+            let mut chars = input.chars();
+            let mut lexer = Lexer::with_options(&mut chars, &options);
+            for token in numbers {
+                assert_eq!(lexer.consume().unwrap().0, token);
+            }
+            if mode == NumericMode::Mixed {
+                assert!(matches!(lexer.consume().unwrap().0, Token::Float(nan) if nan.is_nan()));
+            }
+            for token in [
+                Token::Identifier("\"key\"".to_string()),
+                Token::Str("\"it's\"".to_string()),
+                Token::Str("\"ab\"".to_string()),
+            ] {
+                assert_eq!(lexer.consume().unwrap().0, token);
+            }
+        }
+    }
+
+    #[test]
+    fn should_name_extensions_when_strict() {
+        for (input, extension, column) in [
+            ("0x1f", Extension::HexNumbers, 1),
+            ("5.]", Extension::TrailingDecimalPoint, 1),
+            ("Infinity", Extension::NonFiniteNumbers, 1),
+            ("'a'", Extension::SingleQuotedStrings, 1),
+            ("\"a\\\nb\"", Extension::MultiLineStrings, 3),
+        ] {
+            let mut chars = input.chars();
+            let mut lexer = Lexer::new(&mut chars);
+            let err = lexer.consume().unwrap_err();
+            assert_eq!(
+                err.details,
+                ParserErrorDetails::ExtensionRequired(extension)
+            );
+            assert_eq!(err.coords.unwrap().column, column, "{}", input);
+        }
+    }
 }
@@ -21,15 +21,22 @@ use std::borrow::Cow;
 use std::collections::HashMap;
 
 pub mod coords;
+pub mod cst;
 pub mod decoders;
+pub mod diagnostics;
 pub mod dom;
 pub mod errors;
 pub mod events;
 pub mod lexer;
+pub mod options;
 pub mod pointer;
+pub mod report;
 pub mod sax;
+pub mod spans;
+pub mod streams;
 #[cfg(test)]
 mod test_macros;
+pub mod writer;
 
 /// Basic enumeration of different Json values
 #[derive(Debug)]
@@ -44,8 +51,13 @@ pub enum JsonValue<'a> {
     Float(f64),
     /// Integer numeric value
     Integer(i64),
+    /// Unconverted numeric value, produced when [options::NumericMode::Raw] is in use
+    RawNumber(Cow<'a, str>),
     /// Canonical boolean value
     Boolean(bool),
     /// Canonical null value
     Null,
+    /// Placeholder for a value which couldn't be parsed, produced when recovering from errors.
+    /// (See [options::ParserOptions::with_recovery])
+    Invalid,
 }
@@ -0,0 +1,841 @@
+//! Options and policies which control the behaviour of both the DOM and SAX parsers
+//!
+//! Both parsers may be configured at runtime through a [ParserOptions] instance, which is built up
+//! using a series of chained `with_` calls:
+//!
+//! ```rust
+//! use chisel_json::decoders::Encoding;
+//! use chisel_json::options::{DuplicateKeyPolicy, ParserOptions};
+//! use chisel_json::dom::Parser;
+//!
+//! let options = ParserOptions::default()
+//!     .with_encoding(Encoding::Utf8)
+//!     .with_duplicate_key_policy(DuplicateKeyPolicy::Error)
+//!     .with_max_depth(32);
+//! let parser = Parser::with_options(options);
+//! assert!(parser.parse_str("{\"a\" : 1, \"a\" : 2}").is_err());
+//! ```
+use crate::coords::{Coords, Span};
+use crate::decoders::Encoding;
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use crate::lexer::{Lexer, PackedToken, Token};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::panic::RefUnwindSafe;
+use std::sync::Arc;
+
+/// The default maximum nesting depth for objects and arrays
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+/// Determines how numbers found within the input are converted
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum NumericMode {
+    /// All numbers are parsed as `f64` values
+    Float,
+    /// Integral numbers are parsed as `i64` values, with everything else parsed as `f64` values
+    Mixed,
+    /// Numbers are not converted, and their original representation is retained
+    Raw,
+}
+
+impl Default for NumericMode {
+    /// The default mode is selected by the `mixed_numerics` feature
+    fn default() -> Self {
+        if cfg!(feature = "mixed_numerics") {
+            Self::Mixed
+        } else {
+            Self::Float
+        }
+    }
+}
+
+/// Limits applied to the input during a parse, intended to protect against hostile or runaway
+/// payloads. With the exception of the nesting depth, all limits are disabled by default
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Limits {
+    /// The maximum nesting depth of objects and arrays
+    pub max_depth: usize,
+    /// The maximum size of the input in bytes, measured in the same terms as [Coords::offset], (so
+    /// in terms of the source encoding, or of UTF-8 when parsing a `&str` or stream of `char`s)
+    pub max_input_length: Option<usize>,
+    /// The maximum number of characters within a single string, excluding the enclosing quotes
+    pub max_string_length: Option<usize>,
+    /// The maximum number of characters within the representation of a single number
+    pub max_number_length: Option<usize>,
+    /// The maximum number of members within a single object or array
+    pub max_members: Option<usize>,
+    /// The maximum total number of values (objects, arrays and scalars) within the input
+    pub max_nodes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: DEFAULT_MAX_DEPTH,
+            max_input_length: None,
+            max_string_length: None,
+            max_number_length: None,
+            max_members: None,
+            max_nodes: None,
+        }
+    }
+}
+
+/// Runtime configuration shared by both the DOM and SAX parsers
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ParserOptions {
+    /// The [Encoding] used to decode byte-oriented input
+    pub encoding: Encoding,
+    /// The [NumericMode] used to convert numbers
+    pub numeric_mode: NumericMode,
+    /// The [DuplicateKeyPolicy] applied to objects
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// The [Limits] enforced during a parse
+    pub limits: Limits,
+    /// Whether to recover from errors and carry on parsing, rather than stopping at the first error
+    pub recover: bool,
+    /// Whether the DOM parser should record the [Span](crate::coords::Span)s of every value
+    pub spans: bool,
+    /// The [CommentPolicy] applied to comments found within the input
+    pub comments: CommentPolicy,
+    /// The [Dialect] of JSON accepted by the parsers
+    pub dialect: Dialect,
+    /// Whether the `NaN`, `Infinity` and `-Infinity` literals are accepted as numbers
+    pub non_finite_numbers: bool,
+    /// Whether a single trailing comma is accepted before the closing bracket of an object or
+    /// array
+    pub trailing_commas: bool,
+    /// The [WarningHook] called whenever the parsers tolerate non-standard input
+    pub warning_hook: Option<WarningHook>,
+    /// Whether records which can't be parsed are skipped when parsing a stream of records
+    pub skip_invalid_records: bool,
+}
+
+impl ParserOptions {
+    /// Set the [Encoding] used to decode byte-oriented input
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Set the [NumericMode] used to convert any numbers encountered during a parse
+    pub fn with_numeric_mode(mut self, mode: NumericMode) -> Self {
+        self.numeric_mode = mode;
+        self
+    }
+
+    /// Set the [DuplicateKeyPolicy] applied to any objects encountered during a parse.
+    ///
+    /// Note that in order to honour [DuplicateKeyPolicy::LastWins], the SAX parser holds back the
+    /// events associated with the members of an object until the end of the object is reached
+    pub fn with_duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+
+    /// Set the [Limits] to be enforced during a parse
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Enable or disable recovery from errors. When enabled, the parsers record an error and then
+    /// skip to the next comma or closing bracket, carrying on from there. The DOM parser inserts
+    /// [JsonValue::Invalid](crate::JsonValue::Invalid) placeholders for any values which couldn't
+    /// be parsed, and every error found is returned within the
+    /// [ParseReport](crate::report::ParseReport) of the parse.
+    ///
+    /// Errors raised by the decoder, at the end of the input, or by exceeding one of the [Limits]
+    /// still stop the parse
+    pub fn with_recovery(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    /// Enable or disable the recording of spans by the DOM parser. When enabled, a
+    /// [SpanTable](crate::spans::SpanTable) locating every value and object key within the input
+    /// is returned within the [ParseReport](crate::report::ParseReport) of each parse
+    pub fn with_spans(mut self, spans: bool) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Set the [CommentPolicy] applied to any `//` line comments and `/* */` block comments found
+    /// within the input, (as found within JSONC configuration files)
+    pub fn with_comment_policy(mut self, policy: CommentPolicy) -> Self {
+        self.comments = policy;
+        self
+    }
+
+    /// Set the [Dialect] of JSON accepted by the parsers. Selecting [Dialect::Json5] enables every
+    /// [Extension], and skips over comments unless a [CommentPolicy] of
+    /// [CommentPolicy::Report] is also in use
+    pub fn with_dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    /// Enable or disable the `NaN`, `Infinity` and `-Infinity` literals (as emitted by Python's
+    /// `json` module, amongst others), independently of the [Dialect] in use. The literals are
+    /// converted to [f64::NAN], [f64::INFINITY] and [f64::NEG_INFINITY] respectively, unless
+    /// [NumericMode::Raw] is in use. A [crate::writer::Writer] created from the same options will
+    /// write them back out, rather than rejecting them
+    pub fn with_non_finite_numbers(mut self, enabled: bool) -> Self {
+        self.non_finite_numbers = enabled;
+        self
+    }
+
+    /// Enable or disable the acceptance of a single trailing comma before the closing bracket of
+    /// an object or array, (as in `[1, 2, 3,]` or `{"a" : 1,}`), independently of the [Dialect]
+    /// in use. Each trailing comma accepted is passed to the [WarningHook] (if any), so that it
+    /// may still be flagged
+    pub fn with_trailing_commas(mut self, enabled: bool) -> Self {
+        self.trailing_commas = enabled;
+        self
+    }
+
+    /// Set a hook which is called with a [ParserWarning] whenever the parsers tolerate input
+    /// which isn't strictly valid JSON, such as a trailing comma. The hook must be
+    /// [RefUnwindSafe], so that parsers remain usable within [std::panic::catch_unwind]
+    pub fn with_warning_hook(
+        mut self,
+        hook: impl Fn(&ParserWarning) + Send + Sync + RefUnwindSafe + 'static,
+    ) -> Self {
+        self.warning_hook = Some(WarningHook(Arc::new(hook)));
+        self
+    }
+
+    /// Enable or disable the skipping of records which can't be parsed, when parsing a stream of
+    /// records (see [crate::streams]). When enabled, the error raised by each record skipped is
+    /// retained, and parsing carries on with the next record. Otherwise, the first such error
+    /// stops the parse
+    pub fn with_skip_invalid_records(mut self, skip: bool) -> Self {
+        self.skip_invalid_records = skip;
+        self
+    }
+
+    /// Get the set of [Extension]s enabled by the options
+    pub(crate) fn extensions(&self) -> Extensions {
+        let mut extensions = Extensions::default();
+        if self.dialect == Dialect::Json5 {
+            extensions = Extensions::all();
+        }
+        if self.comments != CommentPolicy::Reject {
+            extensions = extensions.with(Extension::Comments);
+        }
+        if self.non_finite_numbers {
+            extensions = extensions.with(Extension::NonFiniteNumbers);
+        }
+        if self.trailing_commas {
+            extensions = extensions.with(Extension::TrailingCommas);
+        }
+        extensions
+    }
+
+    /// Set the maximum nesting depth of objects and arrays allowed within the input. Exceeding this
+    /// depth results in a [ParserErrorDetails::MaxDepthExceeded] error, rather than the exhaustion
+    /// of the stack
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.limits.max_depth = depth;
+        self
+    }
+}
+
+/// Tracks the nesting depth and number of values seen during a single parse, so that the
+/// structural [Limits] can be enforced by the parsers
+pub(crate) struct LimitTracker {
+    /// The limits to enforce
+    limits: Limits,
+    /// The current nesting depth
+    depth: usize,
+    /// The number of values seen so far
+    nodes: usize,
+}
+
+impl LimitTracker {
+    /// Create a new tracker for a given set of [Limits]
+    pub fn new(limits: Limits) -> Self {
+        LimitTracker {
+            limits,
+            depth: 0,
+            nodes: 0,
+        }
+    }
+
+    /// Enter a new object or array
+    #[inline]
+    pub fn enter(&mut self) -> Result<(), ParserErrorDetails> {
+        self.depth += 1;
+        if self.depth > self.limits.max_depth {
+            return Err(ParserErrorDetails::MaxDepthExceeded(self.limits.max_depth));
+        }
+        Ok(())
+    }
+
+    /// Leave the current object or array
+    #[inline]
+    pub fn exit(&mut self) {
+        self.depth -= 1;
+    }
+
+    /// Account for a new value within the input
+    #[inline]
+    pub fn node(&mut self) -> Result<(), ParserErrorDetails> {
+        self.nodes += 1;
+        match self.limits.max_nodes {
+            Some(max) if self.nodes > max => Err(ParserErrorDetails::TooManyNodes(max)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the number of members seen so far within the current object or array
+    #[inline]
+    pub fn members(&self, count: usize) -> Result<(), ParserErrorDetails> {
+        match self.limits.max_members {
+            Some(max) if count > max => Err(ParserErrorDetails::TooManyMembers(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Collects the errors found during a single parse when recovery is enabled
+pub(crate) struct Recovery {
+    /// Whether recovery is enabled
+    enabled: bool,
+    /// The errors recovered from so far
+    errors: Vec<ParserError>,
+}
+
+impl Recovery {
+    /// Create a new collector
+    pub fn new(enabled: bool) -> Self {
+        Recovery {
+            enabled,
+            errors: vec![],
+        }
+    }
+
+    /// Attempt to recover from an error by recording it and resynchronising the [Lexer]. The
+    /// comma or closing bracket at which parsing should resume is returned. If recovery is
+    /// disabled or not possible, the error is returned instead
+    pub fn recover<'l>(
+        &mut self,
+        lexer: &'l mut Lexer,
+        error: ParserError,
+    ) -> ParserResult<PackedToken<'l>> {
+        if !self.enabled || !error.is_recoverable() || lexer.at_end() {
+            return Err(error);
+        }
+        self.errors.push(error);
+        lexer.resync()
+    }
+
+    /// Record the error within a result and carry on, if recovery is enabled
+    pub fn tolerate(&mut self, result: ParserResult<()>) -> ParserResult<()> {
+        match result {
+            Err(error) if self.enabled => {
+                self.errors.push(error);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Mark the number of errors recorded so far, so that any errors recorded after this point can
+    /// be attributed to a particular member or element
+    #[inline]
+    pub fn mark(&self) -> usize {
+        self.errors.len()
+    }
+
+    /// Prefix the pointers of any errors recorded since a given mark with a member name
+    #[inline]
+    pub fn within_name(&mut self, mark: usize, name: &str) {
+        for error in self.errors.iter_mut().skip(mark) {
+            error.prefix_name(name);
+        }
+    }
+
+    /// Prefix the pointers of any errors recorded since a given mark with an element index
+    #[inline]
+    pub fn within_index(&mut self, mark: usize, index: usize) {
+        for error in self.errors.iter_mut().skip(mark) {
+            error.prefix_index(index);
+        }
+    }
+
+    /// Consume the collector, returning the errors recorded along with the error (if any) which
+    /// stopped the parse
+    pub fn finish<T>(mut self, result: &ParserResult<T>) -> Vec<ParserError> {
+        if let Err(error) = result {
+            self.errors.push(error.clone());
+        }
+        self.errors
+    }
+}
+
+/// RFC 8259 leaves the handling of duplicate keys within an object undefined. This enumeration
+/// determines what the parsers should do when a key is repeated within a single object
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DuplicateKeyPolicy {
+    /// Keep every occurrence of a key, in the order they appear within the input
+    KeepAll,
+    /// Keep the first occurrence of a key, and discard any later occurrences
+    FirstWins,
+    /// Keep the value of the last occurrence of a key, in the position of the first occurrence
+    LastWins,
+    /// Treat a repeated key as an error
+    Error,
+}
+
+impl Default for DuplicateKeyPolicy {
+    /// By default, all keys are retained
+    fn default() -> Self {
+        Self::KeepAll
+    }
+}
+
+/// Comments aren't part of RFC 8259, but are commonly found within configuration files (JSONC).
+/// This enumeration determines how the parsers treat `//` line comments and `/* */` block comments
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CommentPolicy {
+    /// Treat a comment as an error, as required by RFC 8259
+    Reject,
+    /// Skip over comments as if they were whitespace
+    Skip,
+    /// Skip over comments as if they were whitespace, but report each comment to the SAX parser
+    /// callback as a [Match::Comment](crate::events::Match::Comment) event. The DOM parser treats
+    /// this in the same way as [CommentPolicy::Skip]
+    Report,
+}
+
+impl Default for CommentPolicy {
+    /// By default, comments are rejected
+    fn default() -> Self {
+        Self::Reject
+    }
+}
+
+/// A warning about input which was tolerated by the parsers, but isn't strictly valid JSON
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParserWarning {
+    /// A trailing comma was accepted, at the given [Coords]
+    TrailingComma(Coords),
+}
+
+impl Display for ParserWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParserWarning::TrailingComma(coords) => write!(f, "trailing comma at {}", coords),
+        }
+    }
+}
+
+/// A shareable hook, called with each [ParserWarning] raised during a parse. (See
+/// [ParserOptions::with_warning_hook])
+#[derive(Clone)]
+pub struct WarningHook(Arc<dyn Fn(&ParserWarning) + Send + Sync + RefUnwindSafe>);
+
+impl WarningHook {
+    /// Call the hook with a given [ParserWarning]
+    pub fn warn(&self, warning: &ParserWarning) {
+        (self.0)(warning)
+    }
+}
+
+impl Debug for WarningHook {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "WarningHook")
+    }
+}
+
+impl PartialEq for WarningHook {
+    /// Hooks are only equal if they share the same underlying function
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The dialects of JSON which may be accepted by the parsers
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Dialect {
+    /// Strict JSON, as defined by RFC 8259
+    Json,
+    /// [JSON5](https://spec.json5.org), which enables every [Extension]
+    Json5,
+}
+
+impl Default for Dialect {
+    /// By default, only strict JSON is accepted
+    fn default() -> Self {
+        Self::Json
+    }
+}
+
+/// The individual extensions to strict JSON which may be enabled through [ParserOptions]. When a
+/// parser finds one of these within the input without the extension being enabled, it raises a
+/// [ParserErrorDetails::ExtensionRequired] error naming the extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Extension {
+    /// `//` line comments and `/* */` block comments
+    Comments,
+    /// Object keys which are identifiers, rather than strings
+    UnquotedKeys,
+    /// Strings enclosed in single quotes
+    SingleQuotedStrings,
+    /// A single comma following the last member of an object or element of an array
+    TrailingCommas,
+    /// Hexadecimal numbers such as `0x1F`
+    HexNumbers,
+    /// Numbers such as `.5`, with a decimal point but no integral part
+    LeadingDecimalPoint,
+    /// Numbers such as `5.`, with a decimal point but no fractional part
+    TrailingDecimalPoint,
+    /// Numbers with an explicit leading `+` sign
+    PlusSign,
+    /// The `NaN`, `Infinity` and `-Infinity` literals
+    NonFiniteNumbers,
+    /// Strings which continue onto the next line following an escaped line break
+    MultiLineStrings,
+}
+
+impl Extension {
+    /// Describe how the extension may be enabled
+    pub fn enabled_by(&self) -> &'static str {
+        match self {
+            Extension::Comments => "a comment policy or the JSON5 dialect",
+            Extension::NonFiniteNumbers => "the non-finite numbers option or the JSON5 dialect",
+            Extension::TrailingCommas => "the trailing commas option or the JSON5 dialect",
+            _ => "the JSON5 dialect",
+        }
+    }
+}
+
+impl Display for Extension {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Extension::Comments => write!(f, "comments"),
+            Extension::UnquotedKeys => write!(f, "unquoted keys"),
+            Extension::SingleQuotedStrings => write!(f, "single-quoted strings"),
+            Extension::TrailingCommas => write!(f, "trailing commas"),
+            Extension::HexNumbers => write!(f, "hexadecimal numbers"),
+            Extension::LeadingDecimalPoint => write!(f, "leading decimal points"),
+            Extension::TrailingDecimalPoint => write!(f, "trailing decimal points"),
+            Extension::PlusSign => write!(f, "leading '+' signs"),
+            Extension::NonFiniteNumbers => write!(f, "NaN and Infinity"),
+            Extension::MultiLineStrings => write!(f, "multi-line strings"),
+        }
+    }
+}
+
+/// A set of enabled [Extension]s
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Extensions(u16);
+
+impl Extensions {
+    /// Every [Extension]
+    pub fn all() -> Self {
+        Extensions(u16::MAX)
+    }
+
+    /// Add an [Extension] to the set
+    pub fn with(self, extension: Extension) -> Self {
+        Extensions(self.0 | 1 << extension as u16)
+    }
+
+    /// Check whether an [Extension] is within the set
+    #[inline]
+    pub fn allows(self, extension: Extension) -> bool {
+        self.0 & 1 << extension as u16 != 0
+    }
+
+    /// Check whether any of the number-related [Extension]s are within the set
+    #[inline]
+    pub fn allows_extended_numbers(self) -> bool {
+        self.allows(Extension::HexNumbers)
+            || self.allows(Extension::LeadingDecimalPoint)
+            || self.allows(Extension::TrailingDecimalPoint)
+            || self.allows(Extension::PlusSign)
+            || self.allows(Extension::NonFiniteNumbers)
+    }
+}
+
+/// Decode any escape sequences within a key, so that keys which only differ in the way in which
+/// they're escaped are recognised as naming the same member. Escapes have already been validated
+/// by the lexer, so anything unexpected (such as an unpaired surrogate) is left as it stands
+fn unescape_key(key: &str) -> Cow<'_, str> {
+    if !key.contains('\\') {
+        return Cow::Borrowed(key);
+    }
+    let chars: Vec<char> = key.chars().collect();
+    let hex = |from: usize, len: usize| -> Option<u32> {
+        let digits: String = chars.get(from..from + len)?.iter().collect();
+        u32::from_str_radix(&digits, 16).ok()
+    };
+    let mut decoded = String::with_capacity(key.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        i += 1;
+        if ch != '\\' || i == chars.len() {
+            decoded.push(ch);
+            continue;
+        }
+        let escaped = chars[i];
+        i += 1;
+        let (value, len) = match escaped {
+            'b' => (Some('\u{8}'), 0),
+            'f' => (Some('\u{c}'), 0),
+            'n' => (Some('\n'), 0),
+            'r' => (Some('\r'), 0),
+            't' => (Some('\t'), 0),
+            'u' => match (hex(i, 4), chars.get(i + 4..i + 6), hex(i + 6, 4)) {
+                (Some(high @ 0xd800..=0xdbff), Some(['\\', 'u']), Some(low @ 0xdc00..=0xdfff)) => (
+                    char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)),
+                    10,
+                ),
+                (unit, _, _) => (unit.and_then(char::from_u32), 4),
+            },
+            // Line continuations
+            '\r' if chars.get(i) == Some(&'\n') => {
+                i += 1;
+                continue;
+            }
+            '\r' | '\n' | '\u{2028}' | '\u{2029}' => continue,
+            other => (Some(other), 0),
+        };
+        match value {
+            Some(value) => {
+                decoded.push(value);
+                i += len;
+            }
+            None => {
+                decoded.push(ch);
+                decoded.push(escaped);
+            }
+        }
+    }
+    Cow::Owned(decoded)
+}
+
+/// The outcome of observing a key within an object, based on the current [DuplicateKeyPolicy]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum KeyOutcome {
+    /// The key should be added as a new member of the object
+    Insert,
+    /// The key (and associated value) should be parsed and then discarded
+    Discard,
+    /// The value associated with the key should replace the member at the given index
+    Replace(usize),
+    /// The key has already been seen at the given [Coords], and should be rejected
+    Reject(Coords),
+}
+
+/// Tracks the commas separating the members of an object or the elements of an array, so that
+/// missing, repeated and (unless [Extension::TrailingCommas] is enabled) trailing commas may be
+/// rejected
+pub(crate) struct Separators {
+    /// The source attributed to any errors
+    source: ParserErrorSource,
+    /// Whether a trailing comma is allowed
+    trailing: bool,
+    /// The hook called when a trailing comma is accepted
+    hook: Option<WarningHook>,
+    /// Whether a member has been seen
+    started: bool,
+    /// The [Coords] of the comma following the last member, if there is one
+    comma: Option<Coords>,
+}
+
+impl Separators {
+    /// Create a new tracker for a single object or array, which attributes errors to a given source
+    pub fn new(options: &ParserOptions, source: ParserErrorSource) -> Self {
+        Separators {
+            source,
+            trailing: options.extensions().allows(Extension::TrailingCommas),
+            hook: options.warning_hook.clone(),
+            started: false,
+            comma: None,
+        }
+    }
+
+    /// Observe the start of a member, which must follow a comma unless it's the first member
+    pub fn member(&mut self, token: &Token, span: Span) -> ParserResult<()> {
+        if self.started && self.comma.is_none() {
+            return self.error(
+                ParserErrorDetails::UnexpectedToken(token.clone()),
+                span.start,
+            );
+        }
+        self.started = true;
+        self.comma = None;
+        Ok(())
+    }
+
+    /// Observe a comma, which must follow a member
+    pub fn comma(&mut self, span: Span) -> ParserResult<()> {
+        if !self.started || self.comma.is_some() {
+            return self.error(
+                ParserErrorDetails::UnexpectedToken(Token::Comma),
+                span.start,
+            );
+        }
+        self.comma = Some(span.start);
+        Ok(())
+    }
+
+    /// Observe the closing bracket, which may only follow a comma if trailing commas are allowed.
+    /// Any trailing comma accepted is passed to the [WarningHook]
+    pub fn end(&mut self) -> ParserResult<()> {
+        match self.comma {
+            Some(comma) if !self.trailing => self.error(
+                ParserErrorDetails::ExtensionRequired(Extension::TrailingCommas),
+                comma,
+            ),
+            Some(comma) => {
+                if let Some(hook) = &self.hook {
+                    hook.warn(&ParserWarning::TrailingComma(comma));
+                }
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Resume after recovering from an error at a given comma
+    pub fn resume(&mut self, comma: Span) {
+        self.started = true;
+        self.comma = Some(comma.start);
+    }
+
+    fn error(&self, details: ParserErrorDetails, coords: Coords) -> ParserResult<()> {
+        Err(ParserError {
+            source: self.source,
+            details,
+            coords: Some(coords),
+            cause: None,
+            pointer: None,
+        })
+    }
+}
+
+/// Tracks the keys seen within a single object so that a [DuplicateKeyPolicy] may be applied
+pub(crate) struct KeyTracker {
+    /// The policy to apply
+    policy: DuplicateKeyPolicy,
+    /// Keys seen so far, along with their member index and starting [Coords]
+    keys: HashMap<String, (usize, Coords)>,
+    /// The number of distinct members seen so far
+    members: usize,
+}
+
+impl KeyTracker {
+    /// Create a new tracker for a given [DuplicateKeyPolicy]
+    pub fn new(policy: DuplicateKeyPolicy) -> Self {
+        KeyTracker {
+            policy,
+            keys: HashMap::new(),
+            members: 0,
+        }
+    }
+
+    /// Observe a key found at a given [Coords], and decide what should be done with it
+    pub fn observe(&mut self, key: &str, coords: Coords) -> KeyOutcome {
+        if self.policy == DuplicateKeyPolicy::KeepAll {
+            return KeyOutcome::Insert;
+        }
+        let key = unescape_key(key);
+        match self.keys.get(key.as_ref()) {
+            Some(&(index, first)) => match self.policy {
+                DuplicateKeyPolicy::FirstWins => KeyOutcome::Discard,
+                DuplicateKeyPolicy::LastWins => KeyOutcome::Replace(index),
+                DuplicateKeyPolicy::Error => KeyOutcome::Reject(first),
+                DuplicateKeyPolicy::KeepAll => KeyOutcome::Insert,
+            },
+            None => {
+                self.keys.insert(key.into_owned(), (self.members, coords));
+                self.members += 1;
+                KeyOutcome::Insert
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::coords::Coords;
+    use crate::decoders::Encoding;
+    use crate::errors::ParserErrorDetails;
+    use crate::options::{
+        DuplicateKeyPolicy, KeyOutcome, KeyTracker, LimitTracker, Limits, ParserOptions,
+    };
+
+    #[test]
+    fn keep_all_should_always_insert() {
+        let mut tracker = KeyTracker::new(DuplicateKeyPolicy::KeepAll);
+        assert_eq!(tracker.observe("a", Coords::default()), KeyOutcome::Insert);
+        assert_eq!(tracker.observe("a", Coords::default()), KeyOutcome::Insert);
+    }
+
+    #[test]
+    fn duplicates_should_be_resolved_according_to_policy() {
+        let first = Coords {
+            absolute: 2,
+            line: 1,
+            column: 2,
+            offset: 1,
+            utf16_column: 2,
+        };
+        for (policy, expected) in [
+            (DuplicateKeyPolicy::FirstWins, KeyOutcome::Discard),
+            (DuplicateKeyPolicy::LastWins, KeyOutcome::Replace(1)),
+            (DuplicateKeyPolicy::Error, KeyOutcome::Reject(first)),
+        ] {
+            let mut tracker = KeyTracker::new(policy);
+            assert_eq!(tracker.observe("z", Coords::default()), KeyOutcome::Insert);
+            assert_eq!(tracker.observe("a", first), KeyOutcome::Insert);
+            assert_eq!(tracker.observe("a", Coords::default()), expected);
+        }
+    }
+
+    #[test]
+    fn limit_tracker_should_enforce_structural_limits() {
+        let mut tracker = LimitTracker::new(Limits {
+            max_depth: 1,
+            max_members: Some(2),
+            max_nodes: Some(2),
+            ..Default::default()
+        });
+        assert!(tracker.enter().is_ok());
+        assert_eq!(
+            tracker.enter(),
+            Err(ParserErrorDetails::MaxDepthExceeded(1))
+        );
+        tracker.exit();
+        assert!(tracker.node().is_ok());
+        assert!(tracker.node().is_ok());
+        assert_eq!(tracker.node(), Err(ParserErrorDetails::TooManyNodes(2)));
+        assert!(tracker.members(2).is_ok());
+        assert_eq!(
+            tracker.members(3),
+            Err(ParserErrorDetails::TooManyMembers(2))
+        );
+    }
+
+    #[test]
+    fn options_should_be_built_by_chaining() {
+        let options = ParserOptions::default()
+            .with_encoding(Encoding::Ascii)
+            .with_duplicate_key_policy(DuplicateKeyPolicy::FirstWins)
+            .with_limits(Limits {
+                max_nodes: Some(10),
+                ..Default::default()
+            })
+            .with_max_depth(4);
+        assert_eq!(options.encoding, Encoding::Ascii);
+        assert_eq!(options.duplicate_keys, DuplicateKeyPolicy::FirstWins);
+        assert_eq!(options.limits.max_nodes, Some(10));
+        assert_eq!(options.limits.max_depth, 4);
+    }
+}
@@ -1,7 +1,13 @@
 //! A representation of a JSON Pointer with associated operations, as per RFC 6901
 //!
 //!
-use std::{borrow::Cow, collections::VecDeque, fmt::Display, ops::Add};
+use std::{
+    borrow::Cow,
+    collections::VecDeque,
+    fmt::Display,
+    hash::{Hash, Hasher},
+    ops::Add,
+};
 
 /// Each pointer is a series of segments delineated by a separator char
 const PATH_SEPARATOR: char = '/';
@@ -36,7 +42,7 @@ impl<'a> Display for JsonPointerComponent<'a> {
 }
 
 /// A structure representing a complete pointer, comprising multiple [JsonPointerComponent]s
-#[derive(Debug, Default, Hash, Eq)]
+#[derive(Debug, Default, Clone, Eq)]
 pub struct JsonPointer<'a> {
     /// The components that go together to make up the pointer
     components: VecDeque<JsonPointerComponent<'a>>,
@@ -48,6 +54,12 @@ impl<'a> PartialEq for JsonPointer<'a> {
     }
 }
 
+impl<'a> Hash for JsonPointer<'a> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
 impl<'a> JsonPointer<'a> {
     /// Returns the number of [JsonPointerComponent]s within the pointer
     pub fn len(&self) -> usize {
@@ -87,11 +99,53 @@ impl<'a> JsonPointer<'a> {
             .push_back(JsonPointerComponent::Index(index))
     }
 
+    /// Push a new [JsonPointerComponent::Name] onto the front of the pointer
+    pub fn push_front_name(&mut self, name: String) {
+        self.push_front(JsonPointerComponent::Name(Cow::Owned(name)))
+    }
+
+    /// Push a new [JsonPointerComponent::Index] onto the front of the pointer
+    pub fn push_front_index(&mut self, index: usize) {
+        self.push_front(JsonPointerComponent::Index(index))
+    }
+
+    /// Push a component onto the front of the pointer, immediately after the root
+    fn push_front(&mut self, component: JsonPointerComponent<'a>) {
+        if self.components.front() == Some(&JsonPointerComponent::Root) {
+            self.components.pop_front();
+        }
+        self.components.push_front(component);
+        self.components.push_front(JsonPointerComponent::Root);
+    }
+
+    /// Iterate over the components of the pointer, including the leading
+    /// [JsonPointerComponent::Root] component of a non-empty pointer
+    pub fn components(&self) -> impl Iterator<Item = &JsonPointerComponent<'a>> {
+        self.components.iter()
+    }
+
     /// Pop the last component off the back of the pointer
     pub fn pop(&mut self) -> Option<JsonPointerComponent<'a>> {
         self.components.pop_back()
     }
 
+    /// Convert into a [JsonPointer] which owns all of its components
+    pub fn into_owned(self) -> JsonPointer<'static> {
+        JsonPointer {
+            components: self
+                .components
+                .into_iter()
+                .map(|c| match c {
+                    JsonPointerComponent::Root => JsonPointerComponent::Root,
+                    JsonPointerComponent::Name(name) => {
+                        JsonPointerComponent::Name(Cow::Owned(name.into_owned()))
+                    }
+                    JsonPointerComponent::Index(index) => JsonPointerComponent::Index(index),
+                })
+                .collect(),
+        }
+    }
+
     /// Checks whether a path matches another path.
     pub fn matches(&self, rhs: &'a JsonPointer) -> bool {
         self.as_str() == rhs.as_str()
@@ -143,22 +197,32 @@ mod tests {
     #[test]
     fn pointers_should_serialise_correctly() {
         let mut s = JsonPointer::default();
-        s.push_names(&vec!["a", "b"]);
+        s.push_names(&["a", "b"]);
         assert_eq!("/a/b", s.as_str())
     }
 
     #[test]
     fn pointers_should_serialise_with_escapes_correctly() {
         let mut s = JsonPointer::default();
-        s.push_names(&vec!["a/b", "c~d"]);
+        s.push_names(&["a/b", "c~d"]);
         s.push_index(3);
         assert_eq!("/a~1b/c~0d/3", s.as_str())
     }
 
+    #[test]
+    fn pushing_to_the_front_should_prefix_pointers_correctly() {
+        let mut s = JsonPointer::default();
+        s.push_front_name("price".to_string());
+        s.push_front_index(42);
+        s.push_front_name("items".to_string());
+        assert_eq!("/items/42/price", s.as_str());
+        assert_eq!(s.len(), 4)
+    }
+
     #[test]
     fn popping_should_shorten_pointers_correctly() {
         let mut s = JsonPointer::default();
-        s.push_names(&vec!["a", "b", "c"]);
+        s.push_names(&["a", "b", "c"]);
         assert_eq!("/a/b/c", s.as_str());
         s.pop();
         assert_eq!("/a/b", s.as_str())
@@ -167,7 +231,7 @@ mod tests {
     #[test]
     fn popping_all_components_should_result_in_empty_pointer() {
         let mut s = JsonPointer::default();
-        s.push_names(&vec!["a", "b", "c"]);
+        s.push_names(&["a", "b", "c"]);
         s.pop();
         s.pop();
         s.pop();
@@ -0,0 +1,50 @@
+//! Information gathered during a single parse, returned alongside the value which it produced
+//!
+//! Each of the parser entry points has a `_with_report` counterpart, which returns an [Outcome]
+//! holding both the result of the parse and a [ParseReport]. Because the report belongs to a single
+//! parse rather than to the parser, a parser may be shared between threads or reused for several
+//! inputs without one parse overwriting the report of another:
+//!
+//! ```rust
+//! use chisel_json::decoders::Encoding;
+//! use chisel_json::dom::Parser;
+//!
+//! let parser = Parser::with_encoding(Encoding::Utf8Lossy);
+//! let outcome = parser.parse_bytes_with_report(b"[\"a\xff\"]");
+//! assert!(outcome.result.is_ok());
+//! assert_eq!(outcome.report.replacements.unwrap().count, 1);
+//! ```
+use crate::decoders::{Encoding, Replacements};
+use crate::errors::{ParserError, ParserResult};
+use crate::spans::SpanTable;
+
+/// Information gathered during a single parse
+#[derive(Debug, Clone, Default)]
+pub struct ParseReport {
+    /// The [Encoding] used to decode the input. When the parser is configured with
+    /// [Encoding::Auto], this is the encoding which was detected. `None` if the input was a stream
+    /// of `char`s, or if the parse failed before decoding started
+    pub encoding: Option<Encoding>,
+    /// A summary of the replacements made whilst decoding the input, when using
+    /// [Encoding::Utf8Lossy]. `None` if no replacements
+    /// were made
+    pub replacements: Option<Replacements>,
+    /// Every error found during the parse. When recovery is enabled through
+    /// [ParserOptions::with_recovery](crate::options::ParserOptions::with_recovery), this includes
+    /// each error which was recovered from, followed by the error (if any) which stopped the
+    /// parse. Otherwise, only the error which stopped the parse is included
+    pub errors: Vec<ParserError>,
+    /// The [SpanTable] recorded by the DOM parser, when span recording is enabled through
+    /// [ParserOptions::with_spans](crate::options::ParserOptions::with_spans). If the parse
+    /// failed, the table only covers the values parsed before the failure
+    pub spans: Option<SpanTable>,
+}
+
+/// The result of a single parse, along with the [ParseReport] gathered whilst producing it
+#[derive(Debug)]
+pub struct Outcome<T> {
+    /// The value produced by the parse, or the error which stopped it
+    pub result: ParserResult<T>,
+    /// Everything else found out during the parse
+    pub report: ParseReport,
+}
@@ -4,8 +4,14 @@ use crate::decoders::{DecoderSelector, Encoding};
 use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
 use crate::events::{Event, Match};
 use crate::lexer::{Lexer, Token};
+use crate::options::{
+    CommentPolicy, DuplicateKeyPolicy, KeyOutcome, KeyTracker, LimitTracker, ParserOptions,
+    Recovery, Separators,
+};
 use crate::pointer::JsonPointer;
+use crate::report::{Outcome, ParseReport};
 use crate::sax_parser_error;
+use crate::streams::Lines;
 use crate::JsonValue;
 use crate::Span;
 use std::borrow::Cow;
@@ -30,10 +36,37 @@ macro_rules! emit_event {
     };
 }
 
+/// Type used for internal callbacks, which either buffer or discard events
+type DynCallback<'c> = &'c mut dyn FnMut(&Event) -> ParserResult<()>;
+
+/// An owned copy of an [Event], held back whilst the members of an object are resolved
+struct BufferedEvent {
+    /// The owned [Match]
+    matched: Match<'static>,
+    /// The [Span] of the original event
+    span: Span,
+    /// The owned [JsonPointer] of the original event
+    pointer: JsonPointer<'static>,
+}
+
+impl BufferedEvent {
+    /// Take an owned copy of an [Event]
+    fn from_event(event: &Event) -> Self {
+        BufferedEvent {
+            matched: event.matched.clone().into_owned(),
+            span: event.span,
+            pointer: event
+                .pointer
+                .map(|p| p.clone().into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
 /// Main JSON parser struct
 pub struct Parser {
     decoders: DecoderSelector,
-    encoding: Encoding,
+    options: ParserOptions,
 }
 
 impl Default for Parser {
@@ -41,7 +74,7 @@ impl Default for Parser {
     fn default() -> Self {
         Self {
             decoders: Default::default(),
-            encoding: Default::default(),
+            options: Default::default(),
         }
     }
 }
@@ -51,55 +84,105 @@ impl Parser {
     pub fn with_encoding(encoding: Encoding) -> Self {
         Self {
             decoders: Default::default(),
-            encoding,
+            options: ParserOptions::default().with_encoding(encoding),
+        }
+    }
+
+    /// Create a new instance of the parser configured using a given set of [ParserOptions]
+    pub fn with_options(options: ParserOptions) -> Self {
+        Self {
+            decoders: Default::default(),
+            options,
         }
     }
 
+    /// Get the [ParserOptions] currently in use by the parser
+    pub fn options(&self) -> &ParserOptions {
+        &self.options
+    }
+
+    /// Parse the contents of a file located at a given path, decoded using the configured
+    /// [Encoding]
     pub fn parse_file<PathLike: AsRef<Path>, Callback>(
         &self,
         path: PathLike,
         cb: &mut Callback,
     ) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        self.parse_file_with_report(path, cb).result
+    }
+
+    /// Parse the contents of a file located at a given path, returning a [ParseReport] alongside
+    /// the result
+    pub fn parse_file_with_report<PathLike: AsRef<Path>, Callback>(
+        &self,
+        path: PathLike,
+        cb: &mut Callback,
+    ) -> Outcome<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
         match File::open(&path) {
             Ok(f) => {
                 let mut reader = BufReader::new(f);
-                let mut chars = self.decoders.new_decoder(&mut reader, self.encoding);
-                self.parse(&mut chars, cb)
-            }
-            Err(_) => {
-                sax_parser_error!(ParserErrorDetails::InvalidFile)
+                let chars = self
+                    .decoders
+                    .new_decoder(&mut reader, self.options.encoding);
+                self.parse_lexer(Lexer::with_decoder(chars, &self.options), false, cb)
             }
+            Err(err) => Outcome {
+                result: sax_parser_error!(ParserErrorDetails::InvalidFile, cause = err),
+                report: ParseReport::default(),
+            },
         }
     }
 
+    /// Parse a slice of bytes, decoded using the configured [Encoding]
     pub fn parse_bytes<Callback>(&self, bytes: &[u8], cb: &mut Callback) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        self.parse_bytes_with_report(bytes, cb).result
+    }
+
+    /// Parse a slice of bytes, returning a [ParseReport] alongside the result
+    pub fn parse_bytes_with_report<Callback>(&self, bytes: &[u8], cb: &mut Callback) -> Outcome<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
         if bytes.is_empty() {
-            return sax_parser_error!(ParserErrorDetails::ZeroLengthInput, Coords::default());
+            return Outcome {
+                result: sax_parser_error!(ParserErrorDetails::ZeroLengthInput, Coords::default()),
+                report: ParseReport::default(),
+            };
         }
         let mut reader = BufReader::new(bytes);
-        let mut chars = self.decoders.default_decoder(&mut reader);
-        self.parse(&mut chars, cb)
+        let chars = self
+            .decoders
+            .new_decoder(&mut reader, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), false, cb)
     }
 
+    /// Parse the bytes underlying a string slice, decoded using the configured [Encoding]
     pub fn parse_str<Callback>(&self, str: &str, cb: &mut Callback) -> ParserResult<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
-        if str.is_empty() {
-            return sax_parser_error!(ParserErrorDetails::ZeroLengthInput, Coords::default());
-        }
-        let mut reader = BufReader::new(str.as_bytes());
-        let mut chars = self.decoders.default_decoder(&mut reader);
-        self.parse(&mut chars, cb)
+        self.parse_str_with_report(str, cb).result
+    }
+
+    /// Parse the bytes underlying a string slice, returning a [ParseReport] alongside the result
+    pub fn parse_str_with_report<Callback>(&self, str: &str, cb: &mut Callback) -> Outcome<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        self.parse_bytes_with_report(str.as_bytes(), cb)
     }
 
-    /// Parse the contents extracted from an instance of [BufRead]
+    /// Parse the contents extracted from an instance of [BufRead], decoded using the configured
+    /// [Encoding]
     pub fn parse_buffer<Callback>(
         &self,
         buffer: &mut impl BufRead,
@@ -108,31 +191,185 @@ impl Parser {
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
-        let mut chars = self.decoders.default_decoder(buffer);
-        self.parse(&mut chars, cb)
+        self.parse_buffer_with_report(buffer, cb).result
     }
 
+    /// Parse the contents extracted from an instance of [BufRead], returning a [ParseReport]
+    /// alongside the result
+    pub fn parse_buffer_with_report<Callback>(
+        &self,
+        buffer: &mut impl BufRead,
+        cb: &mut Callback,
+    ) -> Outcome<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let chars = self.decoders.new_decoder(buffer, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), false, cb)
+    }
+
+    /// Parse a stream of `char`s, emitting [Event]s to a given callback
     pub fn parse<Callback>(
         &self,
         chars: &mut impl Iterator<Item = char>,
         cb: &mut Callback,
     ) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        self.parse_with_report(chars, cb).result
+    }
+
+    /// Parse a stream of `char`s, emitting [Event]s to a given callback and returning a
+    /// [ParseReport] alongside the result
+    pub fn parse_with_report<Callback>(
+        &self,
+        chars: &mut impl Iterator<Item = char>,
+        cb: &mut Callback,
+    ) -> Outcome<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        self.parse_lexer(Lexer::with_options(chars, &self.options), false, cb)
+    }
+
+    /// Parse a stream of newline-delimited JSON records (see [crate::streams]), read from a given
+    /// [BufRead]. The events for each record are emitted to the callback along with the index of
+    /// the record, and the [Span]s of the events are relative to the line holding the record.
+    ///
+    /// When [ParserOptions::with_skip_invalid_records] is in use, records which can't be parsed
+    /// are skipped (after any events emitted before the error was found), and the errors raised by
+    /// them are available through [Parser::parse_lines_with_report]. Errors returned by the
+    /// callback always stop the parse
+    pub fn parse_lines<Callback>(&self, reader: impl BufRead, cb: &mut Callback) -> ParserResult<()>
+    where
+        Callback: FnMut(usize, &Event) -> ParserResult<()>,
+    {
+        self.parse_lines_with_report(reader, cb).result
+    }
+
+    /// Parse a stream of newline-delimited JSON records, returning a [ParseReport] alongside the
+    /// result. The errors within the report are those raised by each record, (whether recovered
+    /// from or skipped), followed by the error (if any) which stopped the parse
+    pub fn parse_lines_with_report<Callback>(
+        &self,
+        reader: impl BufRead,
+        cb: &mut Callback,
+    ) -> Outcome<()>
+    where
+        Callback: FnMut(usize, &Event) -> ParserResult<()>,
+    {
+        let mut lines = Lines::new(reader, ParserErrorSource::SaxParser, self.options.encoding);
+        let mut report = ParseReport::default();
+        let result = loop {
+            match lines.advance() {
+                Ok(true) => (),
+                Ok(false) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+            let index = lines.index();
+            let mut bailed = false;
+            let outcome = self.parse_record(lines.line(), &mut |event| {
+                let result = cb(index, event);
+                bailed = result.is_err();
+                result
+            });
+            let mut errors = outcome.report.errors;
+            if outcome.result.is_err() {
+                errors.pop();
+            }
+            report
+                .errors
+                .extend(errors.into_iter().map(|err| lines.locate(err)));
+            match outcome.result {
+                Ok(_) => (),
+                Err(err) if bailed => break Err(err),
+                Err(err) if self.options.skip_invalid_records => {
+                    report.errors.push(lines.locate(err))
+                }
+                Err(err) => break Err(lines.locate(err)),
+            }
+        };
+        if let Err(err) = &result {
+            report.errors.push(err.clone());
+        }
+        Outcome { result, report }
+    }
+
+    /// Parse a single record from a stream of newline-delimited JSON. Unlike a whole document, a
+    /// record may hold any value at its root
+    fn parse_record<Callback>(&self, bytes: &[u8], cb: &mut Callback) -> Outcome<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let mut reader = BufReader::new(bytes);
+        let chars = self
+            .decoders
+            .new_decoder(&mut reader, self.options.encoding);
+        self.parse_lexer(Lexer::with_decoder(chars, &self.options), true, cb)
+    }
+
+    /// Parse the [Token]s produced by a given [Lexer], emitting [Event]s to a given callback and
+    /// reporting any replacements made whilst decoding the input along with any errors found.
+    /// Scalar values are only accepted at the root when `scalars` is set
+    fn parse_lexer<Callback>(
+        &self,
+        mut lexer: Lexer,
+        scalars: bool,
+        cb: &mut Callback,
+    ) -> Outcome<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let mut recovery = Recovery::new(self.options.recover);
+        let result = self.parse_root(&mut lexer, scalars, &mut recovery, cb);
+        let report = ParseReport {
+            encoding: lexer.encoding(),
+            replacements: lexer.replacements(),
+            errors: recovery.finish(&result),
+            ..Default::default()
+        };
+        Outcome { result, report }
+    }
+
+    fn parse_root<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        scalars: bool,
+        recovery: &mut Recovery,
+        cb: &mut Callback,
+    ) -> ParserResult<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
         let mut pointer = JsonPointer::default();
-        let mut lexer = Lexer::new(chars);
+        let mut limits = LimitTracker::new(self.options.limits);
         match lexer.consume()? {
             (Token::StartObject, span) => {
+                self.check_limit(lexer, limits.node())?;
                 emit_event!(cb, Match::StartOfInput, span)?;
+                self.report_comments(lexer, &pointer, cb)?;
                 emit_event!(cb, Match::StartObject, span, pointer)?;
-                self.parse_object(&mut lexer, &mut pointer, cb)
+                self.parse_object(lexer, &mut pointer, &mut limits, recovery, cb)
+                    .map_err(|err| err.with_pointer(&pointer))
             }
             (Token::StartArray, span) => {
+                self.check_limit(lexer, limits.node())?;
                 emit_event!(cb, Match::StartOfInput, span, pointer)?;
+                self.report_comments(lexer, &pointer, cb)?;
                 emit_event!(cb, Match::StartArray, span, pointer)?;
-                self.parse_array(&mut lexer, &mut pointer, cb)
+                self.parse_array(lexer, &mut pointer, &mut limits, recovery, cb)
+                    .map_err(|err| err.with_pointer(&pointer))
             }
+            (token, span) if scalars => match scalar_match(&token) {
+                Some(matched) => {
+                    self.check_limit(lexer, limits.node())?;
+                    emit_event!(cb, Match::StartOfInput, span, pointer)?;
+                    self.report_comments(lexer, &pointer, cb)?;
+                    emit_event!(cb, matched, span, pointer)
+                }
+                None => sax_parser_error!(ParserErrorDetails::InvalidRootObject, span.start),
+            },
             (_, span) => {
                 sax_parser_error!(ParserErrorDetails::InvalidRootObject, span.start)
             }
@@ -143,137 +380,405 @@ impl Parser {
         &self,
         lexer: &mut Lexer,
         pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
         cb: &mut Callback,
     ) -> ParserResult<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
-        match lexer.consume()? {
+        let token = self.consume(lexer, pointer, cb)?;
+        self.check_limit(lexer, limits.node())?;
+        match token {
             (Token::StartObject, span) => {
                 emit_event!(cb, Match::StartObject, span, pointer)?;
-                self.parse_object(lexer, pointer, cb)
+                self.parse_object(lexer, pointer, limits, recovery, cb)
             }
             (Token::StartArray, span) => {
                 emit_event!(cb, Match::StartArray, span, pointer)?;
-                self.parse_array(lexer, pointer, cb)
-            }
-            (Token::Str(str), span) => {
-                emit_event!(cb, Match::String(Cow::Borrowed(&str)), span, pointer)
-            }
-            (Token::Float(value), span) => {
-                emit_event!(cb, Match::Float(value), span, pointer)
-            }
-            (Token::Integer(value), span) => {
-                emit_event!(cb, Match::Integer(value), span, pointer)
-            }
-            (Token::Boolean(value), span) => {
-                emit_event!(cb, Match::Boolean(value), span, pointer)
-            }
-            (Token::Null, span) => {
-                emit_event!(cb, Match::Null, span, pointer)
+                self.parse_array(lexer, pointer, limits, recovery, cb)
             }
             (token, span) => {
+                if let Some(matched) = scalar_match(&token) {
+                    return emit_event!(cb, matched, span, pointer);
+                }
                 sax_parser_error!(ParserErrorDetails::UnexpectedToken(token), span.start)
             }
         }
     }
 
-    /// An object is just a list of comma separated KV pairs
+    /// An object is just a list of comma separated KV pairs. When recovering from an error, the
+    /// pointer is restored to the object itself before skipping to the next member
     fn parse_object<Callback>(
         &self,
         lexer: &mut Lexer,
         pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
         cb: &mut Callback,
     ) -> ParserResult<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
-        loop {
-            match lexer.consume()? {
-                (Token::Str(str), span) => {
-                    pointer.push_name(str.replace("\"", ""));
-                    emit_event!(cb, Match::ObjectKey(Cow::Borrowed(&str)), span, pointer)?;
-                    let should_be_colon = lexer.consume()?;
-                    match should_be_colon {
-                        (Token::Colon, _) => {
-                            self.parse_value(lexer, pointer, cb)?;
-                            pointer.pop();
-                        }
-                        (_, _) => {
-                            return sax_parser_error!(
-                                ParserErrorDetails::PairExpected,
-                                should_be_colon.1.start
-                            )
-                        }
-                    }
+        self.check_limit(lexer, limits.enter())?;
+        let mut object = ObjectState {
+            buffering: self.options.duplicate_keys == DuplicateKeyPolicy::LastWins,
+            keys: KeyTracker::new(self.options.duplicate_keys),
+            separators: Separators::new(&self.options, ParserErrorSource::SaxParser),
+            members: vec![],
+            count: 0,
+        };
+        let depth = pointer.len();
+        let end = loop {
+            let err =
+                match self.parse_object_step(lexer, pointer, limits, recovery, &mut object, cb) {
+                    Ok(None) => continue,
+                    Ok(Some(span)) => break span,
+                    Err(err) => err.with_pointer(pointer),
+                };
+            truncate(pointer, depth);
+            match recovery.recover(lexer, err)? {
+                (Token::Comma, comma) => object.separators.resume(comma),
+                (_, span) => break span,
+            }
+        };
+        self.end_object(object, end, pointer, limits, cb)
+    }
+
+    /// Parse the next member of an object, returning the [Span] of the closing brace once the end
+    /// of the object is reached
+    fn parse_object_step<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        object: &mut ObjectState,
+        cb: &mut Callback,
+    ) -> ParserResult<Option<Span>>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let (token, span) = self.consume_key(lexer, pointer, cb)?;
+        if let Token::Str(_) | Token::Identifier(_) = token {
+            object.separators.member(&token, span)?;
+        }
+        match token {
+            Token::Str(str) | Token::Identifier(str) => {
+                object.count += 1;
+                self.check_limit(lexer, limits.members(object.count))?;
+                let mut outcome = object.keys.observe(&str, span.start);
+                if let KeyOutcome::Reject(first) = outcome {
+                    recovery.tolerate(
+                        sax_parser_error!(
+                            ParserErrorDetails::DuplicateKey(
+                                str.clone(),
+                                Box::new((first, span.start))
+                            ),
+                            span.start
+                        )
+                        .map_err(|err: ParserError| err.with_pointer(pointer)),
+                    )?;
+                    outcome = KeyOutcome::Discard;
                 }
-                (Token::Comma, _) => (),
-                (Token::EndObject, span) => {
-                    return emit_event!(cb, Match::EndObject, span, pointer);
+                pointer.push_name(str.replace('\"', ""));
+                let mut events: Vec<BufferedEvent> = vec![];
+                let mut record = |e: &Event| {
+                    events.push(BufferedEvent::from_event(e));
+                    Ok(())
+                };
+                let mut discard = |_: &Event| Ok(());
+                let sink: Option<DynCallback> = match outcome {
+                    KeyOutcome::Discard => Some(&mut discard),
+                    _ if object.buffering => Some(&mut record),
+                    _ => None,
+                };
+                match sink {
+                    Some(mut sink) => {
+                        emit_event!(sink, Match::ObjectKey(Cow::Borrowed(&str)), span, pointer)?;
+                        self.parse_member_value(lexer, pointer, limits, recovery, &mut sink)?;
+                    }
+                    None => {
+                        emit_event!(cb, Match::ObjectKey(Cow::Borrowed(&str)), span, pointer)?;
+                        self.parse_member_value(lexer, pointer, limits, recovery, cb)?;
+                    }
                 }
-                (_token, span) => {
-                    return sax_parser_error!(ParserErrorDetails::InvalidArray, span.start)
+                match outcome {
+                    KeyOutcome::Replace(index) => object.members[index] = events,
+                    KeyOutcome::Insert if object.buffering => object.members.push(events),
+                    _ => (),
                 }
+                pointer.pop();
+                Ok(None)
+            }
+            Token::Comma => {
+                object.separators.comma(span)?;
+                Ok(None)
+            }
+            Token::EndObject => {
+                object.separators.end()?;
+                Ok(Some(span))
             }
+            Token::EndOfInput => sax_parser_error!(ParserErrorDetails::EndOfInput, span.start),
+            _ => sax_parser_error!(ParserErrorDetails::InvalidObject, span.start),
+        }
+    }
+
+    /// Emit any buffered members of an object, followed by the end of the object itself
+    fn end_object<Callback>(
+        &self,
+        object: ObjectState,
+        span: Span,
+        pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        cb: &mut Callback,
+    ) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        for event in object.members.into_iter().flatten() {
+            emit_event!(cb, event.matched, event.span, event.pointer)?;
+        }
+        limits.exit();
+        emit_event!(cb, Match::EndObject, span, pointer)
+    }
+
+    /// Consume the next [Token] from the [Lexer], first reporting any comments which precede it
+    #[inline]
+    fn consume<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &JsonPointer,
+        cb: &mut Callback,
+    ) -> ParserResult<(Token, Span)>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let token = lexer.consume()?;
+        self.report_comments(lexer, pointer, cb)?;
+        Ok(token)
+    }
+
+    /// As [Parser::consume], for a [Token] in the position of an object key
+    #[inline]
+    fn consume_key<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &JsonPointer,
+        cb: &mut Callback,
+    ) -> ParserResult<(Token, Span)>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let token = lexer.consume_key()?;
+        self.report_comments(lexer, pointer, cb)?;
+        Ok(token)
+    }
+
+    /// Emit a [Match::Comment] event for each comment skipped over by the [Lexer], when using
+    /// [CommentPolicy::Report]
+    fn report_comments<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &JsonPointer,
+        cb: &mut Callback,
+    ) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        if self.options.comments == CommentPolicy::Report {
+            for (text, span) in lexer.take_comments() {
+                emit_event!(cb, Match::Comment(Cow::Owned(text)), span, pointer)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert the outcome of a [LimitTracker] check into a parser error
+    #[inline]
+    fn check_limit(
+        &self,
+        lexer: &Lexer,
+        check: Result<(), ParserErrorDetails>,
+    ) -> ParserResult<()> {
+        match check {
+            Ok(()) => Ok(()),
+            Err(details) => sax_parser_error!(details, lexer.coords()),
+        }
+    }
+
+    /// Parse the colon separator and value associated with a key within an object
+    fn parse_member_value<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        cb: &mut Callback,
+    ) -> ParserResult<()>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        match self.consume(lexer, pointer, cb)? {
+            (Token::Colon, _) => self.parse_value(lexer, pointer, limits, recovery, cb),
+            (_, span) => sax_parser_error!(ParserErrorDetails::PairExpected, span.start),
         }
     }
 
-    /// An array is just a list of comma separated values
+    /// An array is just a list of comma separated values. When recovering from an error, the
+    /// pointer is restored to the array itself before skipping to the next element
     fn parse_array<Callback>(
         &self,
         lexer: &mut Lexer,
         pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
         cb: &mut Callback,
     ) -> ParserResult<()>
     where
         Callback: FnMut(&Event) -> ParserResult<()>,
     {
-        let mut index = 0;
+        self.check_limit(lexer, limits.enter())?;
+        let mut array = ArrayState {
+            index: 0,
+            count: 0,
+            separators: Separators::new(&self.options, ParserErrorSource::SaxParser),
+        };
+        let depth = pointer.len();
         loop {
-            pointer.push_index(index);
-            match lexer.consume()? {
-                (Token::StartArray, span) => {
-                    emit_event!(cb, Match::StartArray, span, pointer)?;
-                    self.parse_array(lexer, pointer, cb)?;
+            pointer.push_index(array.index);
+            let err = match self.parse_array_step(lexer, pointer, limits, recovery, &mut array, cb)
+            {
+                Ok(None) => {
+                    pointer.pop();
+                    continue;
                 }
-                (Token::EndArray, span) => {
+                Ok(Some(span)) => {
                     pointer.pop();
+                    limits.exit();
                     return emit_event!(cb, Match::EndArray, span, pointer);
                 }
-                (Token::StartObject, span) => {
-                    emit_event!(cb, Match::StartObject, span, pointer)?;
-                    self.parse_object(lexer, pointer, cb)?;
-                }
-                (Token::Str(str), span) => {
-                    emit_event!(cb, Match::String(Cow::Borrowed(&str)), span, pointer)?;
+                Err(err) => err.with_pointer(pointer),
+            };
+            truncate(pointer, depth);
+            match recovery.recover(lexer, err)? {
+                (Token::Comma, comma) => {
+                    array.separators.resume(comma);
+                    array.index += 1;
                 }
-                (Token::Float(value), span) => {
-                    emit_event!(cb, Match::Float(value), span, pointer)?;
-                }
-                (Token::Integer(value), span) => {
-                    emit_event!(cb, Match::Integer(value), span, pointer)?;
-                }
-                (Token::Boolean(value), span) => {
-                    emit_event!(cb, Match::Boolean(value), span, pointer)?;
-                }
-                (Token::Null, span) => emit_event!(cb, Match::Null, span, pointer)?,
-                (Token::Comma, _) => index += 1,
-                (_token, span) => {
-                    return sax_parser_error!(ParserErrorDetails::InvalidArray, span.start);
+                (_, span) => {
+                    limits.exit();
+                    return emit_event!(cb, Match::EndArray, span, pointer);
                 }
             }
-            pointer.pop();
         }
     }
+
+    /// Parse the next element of an array, returning the [Span] of the closing bracket once the
+    /// end of the array is reached
+    fn parse_array_step<Callback>(
+        &self,
+        lexer: &mut Lexer,
+        pointer: &mut JsonPointer,
+        limits: &mut LimitTracker,
+        recovery: &mut Recovery,
+        array: &mut ArrayState,
+        cb: &mut Callback,
+    ) -> ParserResult<Option<Span>>
+    where
+        Callback: FnMut(&Event) -> ParserResult<()>,
+    {
+        let token = self.consume(lexer, pointer, cb)?;
+        match &token {
+            (Token::EndOfInput, span) => {
+                return sax_parser_error!(ParserErrorDetails::EndOfInput, span.start)
+            }
+            (Token::EndArray, _) => array.separators.end()?,
+            (Token::Comma, span) => array.separators.comma(*span)?,
+            (token, span) => {
+                array.separators.member(token, *span)?;
+                array.count += 1;
+                self.check_limit(lexer, limits.members(array.count))?;
+                self.check_limit(lexer, limits.node())?;
+            }
+        }
+        match token {
+            (Token::StartArray, span) => {
+                emit_event!(cb, Match::StartArray, span, pointer)?;
+                self.parse_array(lexer, pointer, limits, recovery, cb)?;
+            }
+            (Token::EndArray, span) => return Ok(Some(span)),
+            (Token::StartObject, span) => {
+                emit_event!(cb, Match::StartObject, span, pointer)?;
+                self.parse_object(lexer, pointer, limits, recovery, cb)?;
+            }
+            (Token::Comma, _) => array.index += 1,
+            (token, span) => match scalar_match(&token) {
+                Some(matched) => emit_event!(cb, matched, span, pointer)?,
+                None => return sax_parser_error!(ParserErrorDetails::InvalidArray, span.start),
+            },
+        }
+        Ok(None)
+    }
+}
+
+/// The state associated with an object whilst its members are parsed
+struct ObjectState {
+    /// Whether member events are buffered until the end of the object
+    buffering: bool,
+    /// The keys seen so far
+    keys: KeyTracker,
+    /// The commas seen so far
+    separators: Separators,
+    /// The buffered events for each member, if buffering
+    members: Vec<Vec<BufferedEvent>>,
+    /// The number of members seen so far
+    count: usize,
+}
+
+/// The state associated with an array whilst its elements are parsed
+struct ArrayState {
+    /// The index of the current element
+    index: usize,
+    /// The number of elements seen so far
+    count: usize,
+    /// The commas seen so far
+    separators: Separators,
+}
+
+/// Convert a scalar [Token] into the corresponding [Match], or `None` if the token isn't a scalar
+fn scalar_match(token: &Token) -> Option<Match<'_>> {
+    match token {
+        Token::Str(str) => Some(Match::String(Cow::Borrowed(str))),
+        Token::Float(value) => Some(Match::Float(*value)),
+        Token::Integer(value) => Some(Match::Integer(*value)),
+        Token::RawNumber(value) => Some(Match::RawNumber(Cow::Borrowed(value))),
+        Token::Boolean(value) => Some(Match::Boolean(*value)),
+        Token::Null => Some(Match::Null),
+        _ => None,
+    }
+}
+
+/// Pop components from a [JsonPointer] until it has a given length
+fn truncate(pointer: &mut JsonPointer, len: usize) {
+    while pointer.len() > len {
+        pointer.pop();
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use crate::decoders::DecoderSelector;
-    use crate::errors::ParserErrorDetails;
+    use crate::decoders::{DecoderSelector, Encoding};
+    use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource};
+    use crate::events::Match;
+    use crate::options::{
+        CommentPolicy, Dialect, DuplicateKeyPolicy, Extension, Limits, NumericMode, ParserOptions,
+        DEFAULT_MAX_DEPTH,
+    };
     use crate::relative_file;
     use crate::sax::Parser;
+    use crate::sax_parser_error;
     use bytesize::ByteSize;
     use std::fs::File;
     use std::io::BufReader;
@@ -323,4 +828,504 @@ mod tests {
         let parser = Parser::default();
         let _parsed = parser.parse_buffer(&mut buffer, &mut |_e| Ok(()));
     }
+
+    #[test]
+    fn should_apply_duplicate_key_policies() {
+        let source = r#"{"a" : {"x" : 1}, "b" : [2], "a" : 3}"#;
+        for (policy, expected) in [
+            (
+                DuplicateKeyPolicy::KeepAll,
+                vec!["/a", "/a", "/a/x", "/a/x", "/b", "/b", "/b/0", "/a", "/a"],
+            ),
+            (
+                DuplicateKeyPolicy::FirstWins,
+                vec!["/a", "/a", "/a/x", "/a/x", "/b", "/b", "/b/0"],
+            ),
+            (
+                DuplicateKeyPolicy::LastWins,
+                vec!["/a", "/a", "/b", "/b", "/b/0"],
+            ),
+        ] {
+            let mut pointers = vec![];
+            let parser =
+                Parser::with_options(ParserOptions::default().with_duplicate_key_policy(policy));
+            parser
+                .parse_str(source, &mut |e| {
+                    match e.matched {
+                        Match::ObjectKey(_)
+                        | Match::StartObject
+                        | Match::StartArray
+                        | Match::Integer(_)
+                        | Match::Float(_) => {
+                            pointers.push(e.pointer.unwrap().to_string());
+                        }
+                        _ => (),
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(pointers[1..], expected[..]);
+        }
+    }
+
+    #[test]
+    fn should_reject_duplicate_keys_when_required() {
+        let parser = Parser::with_options(
+            ParserOptions::default().with_duplicate_key_policy(DuplicateKeyPolicy::Error),
+        );
+        let parsed = parser.parse_str("{\"a\" : 1, \"a\" : 2}", &mut |_e| Ok(()));
+        assert!(parsed.is_err());
+        let err = parsed.err().unwrap();
+        assert_eq!(err.coords.unwrap().absolute, 11);
+        match err.details {
+            ParserErrorDetails::DuplicateKey(key, occurrences) => {
+                assert_eq!(key, "\"a\"");
+                assert_eq!((occurrences.0.absolute, occurrences.1.absolute), (2, 11));
+            }
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn should_fail_cleanly_on_deeply_nested_input() {
+        let parser = Parser::default();
+        for source in ["[".repeat(1_000_000), "{\"a\":".repeat(1_000_000)] {
+            let parsed = parser.parse_str(&source, &mut |_e| Ok(()));
+            assert!(parsed.is_err());
+            let err = parsed.err().unwrap();
+            assert_eq!(
+                err.details,
+                ParserErrorDetails::MaxDepthExceeded(DEFAULT_MAX_DEPTH)
+            );
+            assert!(err.coords.is_some());
+        }
+    }
+
+    #[test]
+    fn should_respect_configured_max_depth() {
+        let parser = Parser::with_options(ParserOptions::default().with_max_depth(3));
+        let parsed = parser.parse_str("[[{\"a\" : []}]]", &mut |_e| Ok(()));
+        assert_eq!(
+            parsed.err().unwrap().details,
+            ParserErrorDetails::MaxDepthExceeded(3)
+        );
+        let parsed = parser.parse_str("[[{\"a\" : 1}]]", &mut |_e| Ok(()));
+        assert!(parsed.is_ok());
+    }
+
+    #[test]
+    fn should_enforce_member_and_node_limits() {
+        let limits = Limits {
+            max_members: Some(3),
+            max_nodes: Some(6),
+            ..Default::default()
+        };
+        let parser = Parser::with_options(ParserOptions::default().with_limits(limits));
+        assert!(parser
+            .parse_str(r#"[1, 2, {"a" : 1, "b" : 2}]"#, &mut |_e| Ok(()))
+            .is_ok());
+        assert_eq!(
+            parser
+                .parse_str("[1, 2, 3, 4]", &mut |_e| Ok(()))
+                .err()
+                .unwrap()
+                .details,
+            ParserErrorDetails::TooManyMembers(3)
+        );
+        assert_eq!(
+            parser
+                .parse_str(r#"{"a" : 1, "b" : 2, "c" : 3, "d" : 4}"#, &mut |_e| Ok(()))
+                .err()
+                .unwrap()
+                .details,
+            ParserErrorDetails::TooManyMembers(3)
+        );
+        assert_eq!(
+            parser
+                .parse_str("[[1, 2], [3, 4]]", &mut |_e| Ok(()))
+                .err()
+                .unwrap()
+                .details,
+            ParserErrorDetails::TooManyNodes(6)
+        );
+    }
+
+    #[test]
+    fn should_honour_encoding_for_all_entry_points() {
+        let ascii = fs::read(relative_file!("fixtures/json/encodings/ascii.json")).unwrap();
+        let utf8 = fs::read(relative_file!("fixtures/json/encodings/utf8.json")).unwrap();
+        for (encoding, accepts_utf8) in [(Encoding::Utf8, true), (Encoding::Ascii, false)] {
+            let parser = Parser::with_encoding(encoding);
+            for (input, ok) in [(&ascii, true), (&utf8, accepts_utf8)] {
+                assert_eq!(parser.parse_bytes(input, &mut |_e| Ok(())).is_ok(), ok);
+                let str = std::str::from_utf8(input).unwrap();
+                assert_eq!(parser.parse_str(str, &mut |_e| Ok(())).is_ok(), ok);
+                let mut buffer = BufReader::new(input.as_slice());
+                assert_eq!(
+                    parser.parse_buffer(&mut buffer, &mut |_e| Ok(())).is_ok(),
+                    ok
+                );
+            }
+            for (path, ok) in [
+                ("fixtures/json/encodings/ascii.json", true),
+                ("fixtures/json/encodings/utf8.json", accepts_utf8),
+            ] {
+                assert_eq!(
+                    parser
+                        .parse_file(relative_file!(path), &mut |_e| Ok(()))
+                        .is_ok(),
+                    ok
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn should_parse_utf16_and_utf32_input() {
+        for (encoding, path) in [
+            (Encoding::Utf16Le, "fixtures/json/encodings/utf16le.json"),
+            (Encoding::Utf16Be, "fixtures/json/encodings/utf16be.json"),
+            (Encoding::Utf32Le, "fixtures/json/encodings/utf32le.json"),
+            (Encoding::Utf32Be, "fixtures/json/encodings/utf32be.json"),
+        ] {
+            let parser = Parser::with_encoding(encoding);
+            let mut strings = vec![];
+            parser
+                .parse_file(relative_file!(path), &mut |e| {
+                    if let Match::String(s) = &e.matched {
+                        strings.push(s.to_string());
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(strings, ["\"naïve café\""]);
+        }
+    }
+
+    #[test]
+    fn should_report_invalid_byte_sequences_with_offsets() {
+        let mut bytes: Vec<u8> = "[1, "
+            .chars()
+            .flat_map(|c| (c as u32).to_be_bytes())
+            .collect();
+        bytes.extend_from_slice(&[0x00, 0x11, 0x00, 0x00]);
+        let err = Parser::with_encoding(Encoding::Utf32Be)
+            .parse_bytes(&bytes, &mut |_e| Ok(()))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::InvalidByteSequence(Encoding::Utf32Be, 16)
+        );
+    }
+
+    #[test]
+    fn should_parse_single_byte_encodings() {
+        let bytes = b"{\"caf\xe9\" : \"\x93quoted\x94\"}";
+        for (encoding, expected) in [
+            (Encoding::Latin1, ["\"café\"", "\"\u{93}quoted\u{94}\""]),
+            (Encoding::Windows1252, ["\"café\"", "\"“quoted”\""]),
+        ] {
+            let mut strings = vec![];
+            Parser::with_encoding(encoding)
+                .parse_bytes(bytes, &mut |e| {
+                    match &e.matched {
+                        Match::ObjectKey(s) | Match::String(s) => strings.push(s.to_string()),
+                        _ => (),
+                    }
+                    Ok(())
+                })
+                .unwrap();
+            assert_eq!(strings, expected);
+        }
+    }
+
+    #[test]
+    fn should_report_replacements_when_lossy() {
+        let parser = Parser::with_encoding(Encoding::Utf8Lossy);
+        let bytes = b"{\n  \"k\xc3\" : \"\xe2\x82\"\n}";
+        let mut strings = vec![];
+        let outcome = parser.parse_bytes_with_report(bytes, &mut |e| {
+            match &e.matched {
+                Match::ObjectKey(s) | Match::String(s) => strings.push(s.to_string()),
+                _ => (),
+            }
+            Ok(())
+        });
+        outcome.result.unwrap();
+        assert_eq!(strings, ["\"k\u{fffd}\"", "\"\u{fffd}\""]);
+        let replacements = outcome.report.replacements.unwrap();
+        assert_eq!(replacements.count, 2);
+        assert_eq!((replacements.first.line, replacements.first.column), (2, 5));
+    }
+
+    #[test]
+    fn errors_should_include_the_pointer_of_the_failure() {
+        let parser = Parser::default();
+        for (input, pointer) in [
+            (
+                "{\"items\" : [{\"price\" : 1}, {\"price\" : 2}, {\"price\" : x}]}",
+                Some("/items/2/price"),
+            ),
+            ("{\"a\" : [1, [2, 3, nul]]}", Some("/a/1/2")),
+            ("{\"a\" : 1, \"b\" 2}", Some("/b")),
+            ("[1, 2", Some("/1")),
+            ("x", None),
+        ] {
+            let err = parser.parse_str(input, &mut |_e| Ok(())).err().unwrap();
+            assert_eq!(err.pointer.map(|p| p.to_string()).as_deref(), pointer);
+        }
+    }
+
+    #[test]
+    fn should_continue_emitting_events_when_recovering() {
+        let input = "{\"a\" : [1, nul, 3], \"b\" : x, \"c\" : true}";
+        let parser = Parser::with_options(
+            ParserOptions::default()
+                .with_numeric_mode(NumericMode::Mixed)
+                .with_recovery(true),
+        );
+        let mut events = vec![];
+        let outcome = parser.parse_str_with_report(input, &mut |e| {
+            events.push(format!(
+                "{} {}",
+                e.pointer.map(|p| p.to_string()).unwrap_or_default(),
+                e.matched
+            ));
+            Ok(())
+        });
+        assert!(outcome.result.is_ok());
+        assert!(events.contains(&"/a/2 Integer(3)".to_string()));
+        assert!(events.contains(&"/c Boolean(true)".to_string()));
+        assert!(events.last().unwrap().ends_with("EndObject"));
+        let pointers: Vec<String> = outcome
+            .report
+            .errors
+            .iter()
+            .map(|err| err.pointer.as_ref().unwrap().to_string())
+            .collect();
+        assert_eq!(pointers, vec!["/a/1", "/b"]);
+    }
+
+    #[test]
+    fn should_stop_at_unrecoverable_errors() {
+        let parser = Parser::with_options(ParserOptions::default().with_recovery(true));
+        let outcome = parser.parse_str_with_report("{\"a\" : x, \"b\" : 1", &mut |_e| Ok(()));
+        let err = outcome.result.err().unwrap();
+        assert_eq!(err.details, ParserErrorDetails::EndOfInput);
+        let errors = outcome.report.errors;
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].details, ParserErrorDetails::InvalidCharacter('x'));
+    }
+
+    #[test]
+    fn should_report_comments_when_required() {
+        let path = relative_file!("fixtures/json/jsonc/settings.jsonc");
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Report);
+        let mut comments = vec![];
+        let mut count = 0;
+        Parser::with_options(options)
+            .parse_file(&path, &mut |e| {
+                count += 1;
+                if let Match::Comment(text) = &e.matched {
+                    let pointer = e.pointer.map(|p| p.to_string()).unwrap_or_default();
+                    comments.push(format!("{} {}", pointer, text));
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            comments,
+            [
+                " // Workspace settings",
+                " /* Editor */",
+                " // spaces",
+                "/editor.rulers/1 /* soft */",
+                "/files.exclude // keep out of search"
+            ]
+        );
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Skip);
+        let mut skipped = 0;
+        Parser::with_options(options)
+            .parse_file(&path, &mut |_| {
+                skipped += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(skipped, count - comments.len());
+        assert!(Parser::default()
+            .parse_file(&path, &mut |_| Ok(()))
+            .is_err());
+    }
+
+    #[test]
+    fn should_emit_json5_events_when_enabled() {
+        let path = relative_file!("fixtures/json/json5/config.json5");
+        let options = ParserOptions::default()
+            .with_numeric_mode(NumericMode::Mixed)
+            .with_dialect(Dialect::Json5);
+        let mut events = vec![];
+        Parser::with_options(options.clone())
+            .parse_file(&path, &mut |e| {
+                match &e.matched {
+                    Match::ObjectKey(key) => events.push(key.to_string()),
+                    Match::String(_) | Match::Integer(_) | Match::Float(_) => {
+                        events.push(e.matched.to_string())
+                    }
+                    _ => (),
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            events,
+            [
+                "\"name\"",
+                "String(\"chisel\")",
+                "\"version\"",
+                "String(\"1.0\")",
+                "\"mask\"",
+                "Integer(255)",
+                "\"ratios\"",
+                "Float(0.5)",
+                "Float(5)",
+                "Integer(1)",
+                "\"limits\"",
+                "\"upper\"",
+                "Float(inf)",
+                "\"lower\"",
+                "Float(-inf)",
+                "\"unset\"",
+                "Float(NaN)",
+                "\"motto\"",
+                "String(\"It's a long line\")"
+            ]
+        );
+        let err = Parser::default()
+            .parse_file(&path, &mut |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::InvalidCharacter('/'));
+        let err = Parser::default()
+            .parse_str("{\"a\" : 'b'}", &mut |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::ExtensionRequired(Extension::SingleQuotedStrings)
+        );
+        assert!(err.to_string().contains("enabled by the JSON5 dialect"));
+        let mut keys = vec![];
+        Parser::with_options(options)
+            .parse_str("{null : 1, false : {Infinity : 2}}", &mut |e| {
+                if let Match::ObjectKey(key) = &e.matched {
+                    keys.push(key.to_string());
+                }
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(keys, ["\"null\"", "\"false\"", "\"Infinity\""]);
+        let err = Parser::default()
+            .parse_str("{port : 1}", &mut |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::ExtensionRequired(Extension::UnquotedKeys)
+        );
+    }
+
+    #[test]
+    fn should_tolerate_single_trailing_commas_when_enabled() {
+        let input = "[[1, 2,], {\"a\" : null,},]";
+        assert!(Parser::default().parse_str(input, &mut |_| Ok(())).is_err());
+        let options = ParserOptions::default().with_trailing_commas(true);
+        let mut events = 0;
+        Parser::with_options(options)
+            .parse_str(input, &mut |_| {
+                events += 1;
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(events, 11);
+        let err = Parser::with_options(ParserOptions::default().with_trailing_commas(true))
+            .parse_str("[1,,]", &mut |_| Ok(()))
+            .unwrap_err();
+        assert_eq!(err.coords.unwrap().column, 4);
+    }
+
+    #[test]
+    fn should_emit_newline_delimited_records_with_their_index() {
+        let path = relative_file!("fixtures/json/ndjson/logs.ndjson");
+        let options = ParserOptions::default().with_skip_invalid_records(true);
+        let parser = Parser::with_options(options);
+        let mut messages = vec![];
+        let outcome = parser.parse_lines_with_report(
+            BufReader::new(File::open(&path).unwrap()),
+            &mut |index, e| {
+                if let (Match::String(msg), Some(pointer)) = (&e.matched, e.pointer) {
+                    if pointer.to_string() == "/msg" {
+                        messages.push(format!("{} {}", index, msg));
+                    }
+                }
+                Ok(())
+            },
+        );
+        outcome.result.unwrap();
+        assert_eq!(messages, ["0 \"started\"", "1 \"slow\"", "4 \"stopped\""]);
+        let errors = outcome.report.errors;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].coords.unwrap().line, 4);
+
+        let err = Parser::default()
+            .parse_lines(BufReader::new(File::open(&path).unwrap()), &mut |_, _| {
+                Ok(())
+            })
+            .unwrap_err();
+        assert_eq!(err.coords.unwrap().line, 4);
+        let mut seen = 0;
+        let err = parser
+            .parse_lines(
+                BufReader::new(File::open(&path).unwrap()),
+                &mut |index, _| {
+                    seen = index;
+                    match index {
+                        1 => sax_parser_error!(ParserErrorDetails::InvalidFile),
+                        _ => Ok(()),
+                    }
+                },
+            )
+            .unwrap_err();
+        assert_eq!((seen, err.details), (1, ParserErrorDetails::InvalidFile));
+    }
+
+    #[test]
+    fn should_emit_scalar_records_and_reject_utf16_lines() {
+        let parser = Parser::default();
+        let mut events = vec![];
+        parser
+            .parse_lines("1\n\"x\"\n[true]\n".as_bytes(), &mut |index, e| {
+                events.push(format!("{} {}", index, e.matched));
+                Ok(())
+            })
+            .unwrap();
+        assert!(events.contains(&"1 String(\"x\")".to_string()));
+        assert!(events.contains(&"2 Boolean(true)".to_string()));
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| e.ends_with("StartOfInput"))
+                .count(),
+            3
+        );
+
+        let bytes: Vec<u8> = "{\"a\":1}\n{\"b\":2}\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let err = Parser::with_encoding(Encoding::Utf16Le)
+            .parse_lines(bytes.as_slice(), &mut |_, _| Ok(()))
+            .unwrap_err();
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::UnsupportedLineEncoding(Encoding::Utf16Le)
+        );
+    }
 }
@@ -0,0 +1,370 @@
+//! Side tables which record the location of every value (and object key) within the parser input
+//!
+//! When span recording is enabled through [ParserOptions::with_spans], the DOM parser builds a
+//! [SpanTable] alongside the [JsonValue](crate::JsonValue) it produces, which is returned within
+//! the [ParseReport](crate::report::ParseReport) of the parse. The table is keyed by the
+//! [JsonPointer] of each value, so that a value found to be wrong after parsing can be reported
+//! against its original location within the input:
+//!
+//! ```rust
+//! use chisel_json::dom::Parser;
+//! use chisel_json::options::ParserOptions;
+//! use chisel_json::pointer::JsonPointer;
+//!
+//! let parser = Parser::with_options(ParserOptions::default().with_spans(true));
+//! let outcome = parser.parse_str_with_report("{\n  \"port\" : -1\n}");
+//! assert!(outcome.result.is_ok());
+//! let spans = outcome.report.spans.unwrap();
+//! let mut pointer = JsonPointer::default();
+//! pointer.push_name("port".to_string());
+//! let port = spans.get(&pointer).unwrap();
+//! assert_eq!((port.value.start.line, port.value.start.column), (2, 12));
+//! ```
+//!
+//! Alternatively, a [SourceMap] may be built from a single pass of the SAX parser. As well as
+//! mapping pointers to spans, a source map can find the innermost value at a given byte offset
+//! within the input, which is the lookup needed for hover and go-to features within an editor.
+use crate::coords::Span;
+use crate::errors::ParserResult;
+use crate::events::Match;
+use crate::options::ParserOptions;
+use crate::pointer::JsonPointer;
+use crate::sax::Parser;
+use std::collections::HashMap;
+
+/// The [Span]s associated with a single value within the input
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct NodeSpans {
+    /// The [Span] of the key associated with the value, if the value is a member of an object
+    pub key: Option<Span>,
+    /// The [Span] of the value itself. For objects and arrays, this runs from the opening bracket
+    /// through to the closing bracket
+    pub value: Span,
+}
+
+/// A table mapping the [JsonPointer] of each value within the input to its [NodeSpans]
+#[derive(Debug, Default, Clone)]
+pub struct SpanTable {
+    /// The spans, keyed by the string representation of each pointer
+    entries: HashMap<String, NodeSpans>,
+}
+
+impl SpanTable {
+    /// Look up the [NodeSpans] of the value at a given [JsonPointer]
+    pub fn get(&self, pointer: &JsonPointer) -> Option<&NodeSpans> {
+        self.entries.get(pointer.as_str().as_ref())
+    }
+
+    /// The number of values within the table
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether the table is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterate over the (string representation of the) pointers and [NodeSpans] within the table,
+    /// in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &NodeSpans)> {
+        self.entries
+            .iter()
+            .map(|(pointer, spans)| (pointer.as_str(), spans))
+    }
+
+    /// Record the spans of the value at a given pointer
+    pub(crate) fn insert(&mut self, pointer: &JsonPointer, spans: NodeSpans) {
+        self.entries.insert(pointer.as_str().into_owned(), spans);
+    }
+
+    /// Get mutable access to the spans of the value at a given pointer
+    fn get_mut(&mut self, pointer: &JsonPointer) -> Option<&mut NodeSpans> {
+        self.entries.get_mut(pointer.as_str().as_ref())
+    }
+}
+
+/// Tracks the pointer of the value currently being parsed, and records the spans of each value
+/// within a [SpanTable]. Does nothing unless span recording is enabled
+pub(crate) struct SpanRecorder {
+    /// Whether spans are being recorded
+    enabled: bool,
+    /// The pointer of the value currently being parsed
+    pointer: JsonPointer<'static>,
+    /// The key spans associated with each component of the pointer
+    keys: Vec<Option<Span>>,
+    /// The spans recorded so far
+    table: SpanTable,
+}
+
+impl SpanRecorder {
+    /// Create a new recorder, which is enabled according to a given set of [ParserOptions]
+    pub fn new(options: &ParserOptions) -> Self {
+        SpanRecorder {
+            enabled: options.spans,
+            pointer: JsonPointer::default(),
+            keys: vec![],
+            table: SpanTable::default(),
+        }
+    }
+
+    /// Descend into the member of an object with a given key
+    #[inline]
+    pub fn push_name(&mut self, name: &str, key: Span) {
+        if self.enabled {
+            self.pointer.push_name(name.replace('\"', ""));
+            self.keys.push(Some(key));
+        }
+    }
+
+    /// Descend into the element of an array with a given index
+    #[inline]
+    pub fn push_index(&mut self, index: usize) {
+        if self.enabled {
+            self.pointer.push_index(index);
+            self.keys.push(None);
+        }
+    }
+
+    /// Return to the enclosing object or array
+    #[inline]
+    pub fn pop(&mut self) {
+        if self.enabled {
+            self.pointer.pop();
+            self.keys.pop();
+        }
+    }
+
+    /// Suspend recording (if a given condition holds) whilst parsing a value which is to be
+    /// discarded, returning whether recording was previously enabled
+    #[inline]
+    pub fn suspend(&mut self, condition: bool) -> bool {
+        let enabled = self.enabled;
+        self.enabled = enabled && !condition;
+        enabled
+    }
+
+    /// Resume recording after a call to [SpanRecorder::suspend]
+    #[inline]
+    pub fn resume(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Record the span of the value at the current pointer
+    #[inline]
+    pub fn record(&mut self, value: Span) {
+        if self.enabled {
+            let key = self.keys.last().copied().flatten();
+            self.table.insert(&self.pointer, NodeSpans { key, value });
+        }
+    }
+
+    /// Consume the recorder, returning the table of spans if recording was enabled
+    pub fn finish(self) -> Option<SpanTable> {
+        if self.enabled {
+            Some(self.table)
+        } else {
+            None
+        }
+    }
+}
+
+/// A value within a [SourceMap], along with the range of bytes which it covers
+#[derive(Debug, Clone)]
+struct SourceNode {
+    /// The pointer of the value
+    pointer: JsonPointer<'static>,
+    /// The byte offset of the start of the value, or of its key if it has one
+    start: usize,
+    /// The byte offset immediately following the end of the value
+    end: usize,
+    /// The index of the enclosing object or array, if any
+    parent: Option<usize>,
+}
+
+/// An index over the values within some input, which maps each [JsonPointer] to the [NodeSpans]
+/// of its key and value, and each byte offset to the innermost value which covers it
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    /// The spans of each value, keyed by pointer
+    table: SpanTable,
+    /// Every value within the input, in the order in which they start
+    nodes: Vec<SourceNode>,
+}
+
+impl SourceMap {
+    /// Build a source map over some `&str` input, using a default SAX [Parser]
+    pub fn for_str(input: &str) -> ParserResult<Self> {
+        Self::build(&Parser::default(), input.as_bytes())
+    }
+
+    /// Build a source map over some byte input, using a given SAX [Parser] (and therefore the
+    /// encoding and other options configured for the parser) for a single pass over the input
+    pub fn build(parser: &Parser, bytes: &[u8]) -> ParserResult<Self> {
+        let mut map = SourceMap::default();
+        let mut open: Vec<usize> = vec![];
+        let mut key: Option<Span> = None;
+        parser.parse_bytes(bytes, &mut |event| {
+            let pointer = match event.pointer {
+                Some(pointer) => pointer,
+                None => return Ok(()),
+            };
+            match event.matched {
+                Match::StartOfInput | Match::EndOfInput | Match::Comment(_) => (),
+                Match::ObjectKey(_) => key = Some(event.span),
+                Match::EndObject | Match::EndArray => {
+                    if let Some(index) = open.pop() {
+                        map.close(index, event.span);
+                    }
+                }
+                _ => {
+                    let index = map.open(pointer, key.take(), event.span, open.last().copied());
+                    match event.matched {
+                        Match::StartObject | Match::StartArray => open.push(index),
+                        _ => map.close(index, event.span),
+                    }
+                }
+            }
+            Ok(())
+        })?;
+        Ok(map)
+    }
+
+    /// Look up the [NodeSpans] of the value at a given [JsonPointer]
+    pub fn get(&self, pointer: &JsonPointer) -> Option<&NodeSpans> {
+        self.table.get(pointer)
+    }
+
+    /// Find the pointer of the innermost value which covers a given byte offset. The key of an
+    /// object member is treated as part of the member, so an offset within a key maps to the
+    /// pointer of the member
+    pub fn pointer_at(&self, offset: usize) -> Option<&JsonPointer<'static>> {
+        let last = self.nodes.partition_point(|node| node.start <= offset);
+        let mut candidate = last.checked_sub(1);
+        while let Some(index) = candidate {
+            let node = &self.nodes[index];
+            if offset < node.end {
+                return Some(&node.pointer);
+            }
+            candidate = node.parent;
+        }
+        None
+    }
+
+    /// Get the underlying [SpanTable]
+    pub fn spans(&self) -> &SpanTable {
+        &self.table
+    }
+
+    /// Start a new value, returning its index
+    fn open(
+        &mut self,
+        pointer: &JsonPointer,
+        key: Option<Span>,
+        value: Span,
+        parent: Option<usize>,
+    ) -> usize {
+        self.nodes.push(SourceNode {
+            pointer: pointer.clone().into_owned(),
+            start: key.unwrap_or(value).start.offset,
+            end: value.byte_range().end,
+            parent,
+        });
+        self.table.insert(pointer, NodeSpans { key, value });
+        self.nodes.len() - 1
+    }
+
+    /// Complete a value, given the [Span] of its final token
+    fn close(&mut self, index: usize, last: Span) {
+        let node = &mut self.nodes[index];
+        node.end = last.byte_range().end;
+        if let Some(spans) = self.table.get_mut(&node.pointer) {
+            spans.value.end = last.end;
+            spans.value.end_offset = last.end_offset;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{CommentPolicy, ParserOptions};
+    use crate::pointer::JsonPointer;
+    use crate::sax::Parser;
+    use crate::spans::SourceMap;
+
+    /// Build a pointer from its string representation, (without support for escapes)
+    fn pointer(path: &str) -> JsonPointer<'static> {
+        let mut pointer = JsonPointer::default();
+        for component in path.split('/').skip(1) {
+            match component.parse::<usize>() {
+                Ok(index) => pointer.push_index(index),
+                Err(_) => pointer.push_name(component.to_string()),
+            }
+        }
+        pointer
+    }
+
+    #[test]
+    fn should_map_pointers_to_spans() {
+        let input = " {\"name\" : \"café\", \"tags\" : [\"a\", {\"b\" : false}]} ";
+        let map = SourceMap::for_str(input).unwrap();
+        assert_eq!(map.spans().len(), 6);
+        for (path, key, value) in [
+            ("", None, &input[1..input.len() - 1]),
+            ("/name", Some("\"name\""), "\"café\""),
+            ("/tags", Some("\"tags\""), "[\"a\", {\"b\" : false}]"),
+            ("/tags/0", None, "\"a\""),
+            ("/tags/1/b", Some("\"b\""), "false"),
+        ] {
+            let spans = map.get(&pointer(path)).unwrap();
+            assert_eq!(spans.key.map(|key| &input[key.byte_range()]), key);
+            assert_eq!(&input[spans.value.byte_range()], value);
+        }
+    }
+
+    #[test]
+    fn should_find_the_innermost_pointer_at_an_offset() {
+        let input = " {\"name\" : \"café\", \"tags\" : [\"a\", {\"b\" : false}]} ";
+        let map = SourceMap::for_str(input).unwrap();
+        let at = |needle: &str| {
+            let offset = input.find(needle).unwrap();
+            map.pointer_at(offset).map(|p| p.to_string())
+        };
+        assert_eq!(at("name"), Some("/name".to_string()));
+        assert_eq!(at("fé"), Some("/name".to_string()));
+        assert_eq!(at(", \"tags"), Some("".to_string()));
+        assert_eq!(at("[\""), Some("/tags".to_string()));
+        assert_eq!(at("a\""), Some("/tags/0".to_string()));
+        assert_eq!(at("{\"b"), Some("/tags/1".to_string()));
+        assert_eq!(at("alse"), Some("/tags/1/b".to_string()));
+        assert_eq!(map.pointer_at(0), None);
+        assert_eq!(map.pointer_at(input.len() - 1), None);
+    }
+
+    #[test]
+    fn should_skip_over_reported_comments() {
+        let input = "{\"a\" : /* c */ 1, // d\n \"b\" : [/* e */ 2]}";
+        let options = ParserOptions::default().with_comment_policy(CommentPolicy::Report);
+        let map = SourceMap::build(&Parser::with_options(options), input.as_bytes()).unwrap();
+        assert_eq!(map.spans().len(), 4);
+        let spans = map.get(&pointer("/a")).unwrap();
+        assert_eq!(spans.key.map(|key| &input[key.byte_range()]), Some("\"a\""));
+        assert_eq!(&input[spans.value.byte_range()], "1");
+        assert_eq!(
+            &input[map.get(&pointer("/b/0")).unwrap().value.byte_range()],
+            "2"
+        );
+        let at = |needle: &str| {
+            let offset = input.find(needle).unwrap();
+            map.pointer_at(offset).map(|p| p.to_string())
+        };
+        assert_eq!(at("c */"), Some("/a".to_string()));
+        assert_eq!(at("d\n"), Some("".to_string()));
+        assert_eq!(at("e */"), Some("/b".to_string()));
+    }
+
+    #[test]
+    fn should_fail_to_build_over_invalid_input() {
+        assert!(SourceMap::for_str("{\"a\" : x}").is_err());
+    }
+}
@@ -0,0 +1,193 @@
+//! Support for streams made up of multiple JSON records
+//!
+//! [Newline-delimited JSON](https://github.com/ndjson/ndjson-spec) (also known as
+//! [JSON Lines](https://jsonlines.org)) holds a single record on each line of the input. Both
+//! parsers provide a `parse_lines` entry point which works through such a stream a line at a
+//! time, so that records are parsed lazily and the whole stream never needs to be held in memory:
+//!
+//! ```rust
+//! use chisel_json::dom::Parser;
+//! use chisel_json::options::ParserOptions;
+//!
+//! let input = "{\"level\" : \"info\"}\n{\"level\" : \n\n[\"warn\"]\n";
+//! let parser = Parser::with_options(ParserOptions::default().with_skip_invalid_records(true));
+//! let mut records = parser.parse_lines(input.as_bytes());
+//! let lines: Vec<usize> = records.by_ref().map(|r| r.unwrap().line).collect();
+//! assert_eq!(lines, [1, 4]);
+//! assert_eq!(records.skipped()[0].coords.unwrap().line, 2);
+//! ```
+//!
+//! Records may hold any JSON value, including scalars such as `42` or `"text"`, although whole
+//! documents parsed through the other entry points must still have an object or array at their
+//! root. Since lines are found by splitting on line feed bytes, the stream must use an
+//! ASCII-compatible encoding (such as UTF-8, which the JSON Lines format requires). Any other
+//! encoding is rejected with a [ParserErrorDetails::UnsupportedLineEncoding] error.
+//!
+//! Blank lines are ignored. Errors raised whilst parsing a record have their [Coords] adjusted
+//! so that they're relative to the whole stream, and so report the line on which the record was
+//! found
+//!
+//! Some streams instead hold values back-to-back, either simply concatenated (with optional
+//! whitespace between them) or as an [RFC 7464](https://www.rfc-editor.org/rfc/rfc7464) JSON text
+//! sequence, in which each record is preceded by a record separator (`0x1E`). The DOM parser's
+//! `parse_sequence` entry point pulls successive values from such a stream using a single lexer.
+//! A record which is cut short, by a record separator or by the end of the input, is reported as
+//! a [ParserErrorDetails::TruncatedRecord] error. Within a text sequence, parsing then carries on
+//! from the next record:
+//!
+//! ```rust
+//! use chisel_json::dom::Parser;
+//! use chisel_json::errors::ParserErrorDetails;
+//!
+//! let parser = Parser::default();
+//! let mut input = "\u{1e}{\"a\" : 1}\n\u{1e}[1, 2\n\u{1e}[3]\n".as_bytes();
+//! let outcomes: Vec<_> = parser.parse_sequence(&mut input).map(|r| r.map(|r| r.index)).collect();
+//! assert!(matches!(outcomes[0], Ok(0)));
+//! assert!(matches!(&outcomes[1], Err(err) if err.details == ParserErrorDetails::TruncatedRecord));
+//! assert!(matches!(outcomes[2], Ok(2)));
+//! ```
+use crate::coords::Coords;
+use crate::decoders::Encoding;
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use std::io::BufRead;
+use std::sync::Arc;
+
+/// A single record parsed from a stream, along with its position within the stream
+#[derive(Debug)]
+pub struct Record<T> {
+    /// The zero-based index of the record within the stream. Blank lines aren't counted, but
+    /// records which couldn't be parsed are
+    pub index: usize,
+    /// The line on which the record was found
+    pub line: usize,
+    /// The parsed record
+    pub value: T,
+    /// The errors recovered from whilst parsing the record, when recovery is enabled through
+    /// [ParserOptions::with_recovery](crate::options::ParserOptions::with_recovery)
+    pub errors: Vec<ParserError>,
+}
+
+/// Reads a stream of newline-delimited records a line at a time, keeping track of the position
+/// of each line within the stream
+pub(crate) struct Lines<R> {
+    /// The underlying reader
+    reader: R,
+    /// The source attributed to any errors raised by the reader
+    source: ParserErrorSource,
+    /// The encoding of the stream
+    encoding: Encoding,
+    /// The bytes of the current line, without the line break
+    buffer: Vec<u8>,
+    /// The [Coords] of the start of the current line
+    origin: Coords,
+    /// The [Coords] of the start of the next line
+    next: Coords,
+    /// The number of records read so far
+    count: usize,
+}
+
+impl<R: BufRead> Lines<R> {
+    /// Create a new reader over a given [BufRead] holding a stream in a given [Encoding], which
+    /// attributes errors to a given source
+    pub fn new(reader: R, source: ParserErrorSource, encoding: Encoding) -> Self {
+        Lines {
+            reader,
+            source,
+            encoding,
+            buffer: vec![],
+            origin: Coords::default(),
+            next: Coords::default(),
+            count: 0,
+        }
+    }
+
+    /// Move on to the next line which isn't blank, returning `false` at the end of the stream.
+    /// Lines are split on line feed bytes, so an error is raised for any encoding which isn't
+    /// ASCII-compatible
+    pub fn advance(&mut self) -> ParserResult<bool> {
+        if !self.encoding.is_ascii_compatible() {
+            return Err(ParserError {
+                source: self.source,
+                details: ParserErrorDetails::UnsupportedLineEncoding(self.encoding),
+                coords: None,
+                cause: None,
+                pointer: None,
+            });
+        }
+        loop {
+            self.buffer.clear();
+            self.origin = self.next;
+            let read = match self.reader.read_until(b'\n', &mut self.buffer) {
+                Ok(read) => read,
+                Err(err) => {
+                    return Err(ParserError {
+                        source: self.source,
+                        details: ParserErrorDetails::StreamFailure,
+                        coords: Some(self.origin),
+                        cause: Some(Arc::new(err)),
+                        pointer: None,
+                    })
+                }
+            };
+            if read == 0 {
+                return Ok(false);
+            }
+            self.next.line += 1;
+            self.next.offset += read;
+            self.next.absolute += match self.encoding {
+                Encoding::Utf8 | Encoding::Utf8Lossy => {
+                    self.buffer.iter().filter(|b| (**b as i8) >= -0x40).count()
+                }
+                _ => read,
+            };
+            while let Some(b'\n' | b'\r') = self.buffer.last() {
+                self.buffer.pop();
+            }
+            if !self.buffer.iter().all(u8::is_ascii_whitespace) {
+                self.count += 1;
+                return Ok(true);
+            }
+        }
+    }
+
+    /// The bytes of the current line
+    pub fn line(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// The index of the current record
+    pub fn index(&self) -> usize {
+        self.count - 1
+    }
+
+    /// Wrap a value parsed from the current line as a [Record], along with the errors recovered
+    /// from whilst parsing it
+    pub fn record<T>(&self, value: T, errors: Vec<ParserError>) -> Record<T> {
+        Record {
+            index: self.index(),
+            line: self.origin.line,
+            value,
+            errors: errors.into_iter().map(|err| self.locate(err)).collect(),
+        }
+    }
+
+    /// Adjust the [Coords] of an error raised whilst parsing the current line, so that they're
+    /// relative to the whole stream. Errors without coordinates are placed at the start of the line
+    pub fn locate(&self, mut err: ParserError) -> ParserError {
+        match err.coords.as_mut() {
+            Some(coords) => {
+                coords.line += self.origin.line - 1;
+                coords.absolute += self.origin.absolute;
+                coords.offset += self.origin.offset;
+            }
+            None => {
+                err.coords = Some(Coords {
+                    column: 1,
+                    utf16_column: 1,
+                    ..self.origin
+                })
+            }
+        }
+        err
+    }
+}
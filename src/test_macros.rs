@@ -20,8 +20,7 @@ macro_rules! chars_from_file {
     ($f : expr) => {{
         let f = File::open($f).unwrap();
         let mut reader = BufReader::new(f);
-        let decoders = DecoderSelector::default();
-        DecoderSelector::default().default_decoder(&mut reader)
+        DecoderSelector::default().new_decoder(&mut reader, Encoding::default())
     }};
 }
 
@@ -0,0 +1,210 @@
+//! A writer which renders a [JsonValue] back into compact JSON text
+//!
+//! Strings and object keys are written exactly as they're held within the [JsonValue], which (for
+//! values produced by the parsers) is as quoted string literals with their escapes intact. Floats
+//! which aren't finite have no representation within strict JSON, and so are only written (as
+//! `NaN`, `Infinity` and `-Infinity`) when the non-finite numbers extension is enabled:
+//!
+//! ```rust
+//! use chisel_json::dom::Parser;
+//! use chisel_json::options::ParserOptions;
+//! use chisel_json::writer::Writer;
+//!
+//! let options = ParserOptions::default().with_non_finite_numbers(true);
+//! let parser = Parser::with_options(options);
+//! let value = parser.parse_str("{\"mean\" : NaN}").unwrap();
+//! assert!(Writer::default().write_string(&value).is_err());
+//! let writer = Writer::with_options(parser.options());
+//! assert_eq!(writer.write_string(&value).unwrap(), "{\"mean\":NaN}");
+//! ```
+use crate::errors::{ParserError, ParserErrorDetails, ParserErrorSource, ParserResult};
+use crate::options::{Extension, ParserOptions};
+use crate::writer_error;
+use crate::JsonValue;
+use std::io::Write;
+
+/// A writer for [JsonValue]s
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Writer {
+    /// Whether non-finite floats may be written as `NaN`, `Infinity` and `-Infinity`
+    non_finite_numbers: bool,
+}
+
+impl Writer {
+    /// Create a new writer which accepts the same extensions as a parser configured with the given
+    /// [ParserOptions], so that whatever was parsed may be written back out
+    pub fn with_options(options: &ParserOptions) -> Self {
+        Writer {
+            non_finite_numbers: options.extensions().allows(Extension::NonFiniteNumbers),
+        }
+    }
+
+    /// Enable or disable the writing of non-finite floats as `NaN`, `Infinity` and `-Infinity`.
+    /// When disabled, attempting to write a non-finite float results in an error
+    pub fn with_non_finite_numbers(mut self, enabled: bool) -> Self {
+        self.non_finite_numbers = enabled;
+        self
+    }
+
+    /// Write a [JsonValue] to the given output. Any error carries a [crate::pointer::JsonPointer]
+    /// to the value which couldn't be written
+    pub fn write<W: Write>(&self, value: &JsonValue, output: &mut W) -> ParserResult<()> {
+        match value {
+            JsonValue::Object(pairs) => {
+                self.write_raw("{", output)?;
+                for (index, (key, value)) in pairs.iter().enumerate() {
+                    if index > 0 {
+                        self.write_raw(",", output)?;
+                    }
+                    self.write_raw(key, output)?;
+                    self.write_raw(":", output)?;
+                    self.write(value, output)
+                        .map_err(|err| err.within_name(key))?;
+                }
+                self.write_raw("}", output)
+            }
+            JsonValue::Array(values) => {
+                self.write_raw("[", output)?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        self.write_raw(",", output)?;
+                    }
+                    self.write(value, output)
+                        .map_err(|err| err.within_index(index))?;
+                }
+                self.write_raw("]", output)
+            }
+            JsonValue::String(s) => self.write_raw(s, output),
+            JsonValue::Float(f) => self.write_float(*f, output),
+            JsonValue::Integer(i) => self.write_raw(&i.to_string(), output),
+            JsonValue::RawNumber(n) => self.write_raw(n, output),
+            JsonValue::Boolean(true) => self.write_raw("true", output),
+            JsonValue::Boolean(false) => self.write_raw("false", output),
+            JsonValue::Null => self.write_raw("null", output),
+            JsonValue::Invalid => writer_error!(ParserErrorDetails::InvalidValue),
+        }
+    }
+
+    /// Write a [JsonValue] to a new [String]
+    pub fn write_string(&self, value: &JsonValue) -> ParserResult<String> {
+        let mut output = vec![];
+        self.write(value, &mut output)?;
+        Ok(String::from_utf8(output).expect("the writer only produces valid UTF-8"))
+    }
+
+    /// Write a float, rejecting non-finite values unless they've been enabled
+    fn write_float<W: Write>(&self, value: f64, output: &mut W) -> ParserResult<()> {
+        if value.is_finite() {
+            // Exponents are always signed, so that the output may be read back by the lexer
+            let text = format!("{:?}", value);
+            return match text.find('e') {
+                Some(index) if !text[index + 1..].starts_with('-') => {
+                    let (mantissa, exponent) = text.split_at(index + 1);
+                    self.write_raw(&format!("{}+{}", mantissa, exponent), output)
+                }
+                _ => self.write_raw(&text, output),
+            };
+        }
+        if !self.non_finite_numbers {
+            return writer_error!(ParserErrorDetails::ExtensionRequired(
+                Extension::NonFiniteNumbers
+            ));
+        }
+        match value {
+            f if f.is_nan() => self.write_raw("NaN", output),
+            f if f.is_sign_positive() => self.write_raw("Infinity", output),
+            _ => self.write_raw("-Infinity", output),
+        }
+    }
+
+    /// Write some text to the output verbatim
+    #[inline]
+    fn write_raw<W: Write>(&self, text: &str, output: &mut W) -> ParserResult<()> {
+        match output.write_all(text.as_bytes()) {
+            Ok(()) => Ok(()),
+            Err(err) => writer_error!(ParserErrorDetails::StreamFailure, cause = err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dom::Parser;
+    use crate::errors::{ParserErrorDetails, ParserErrorSource};
+    use crate::options::{Dialect, Extension, NumericMode, ParserOptions};
+    use crate::writer::Writer;
+    use crate::JsonValue;
+    use std::io;
+
+    #[test]
+    fn should_round_trip_parsed_values() {
+        let input =
+            "{\"a\" : [1, -2.5, 1e+20, 1.5e-7, \"x\\ny\", true, false, null], \"b\" : {}, \"c\" : []}";
+        for mode in [NumericMode::Mixed, NumericMode::Raw] {
+            let parser = Parser::with_options(ParserOptions::default().with_numeric_mode(mode));
+            let value = parser.parse_str(input).unwrap();
+            let written = Writer::default().write_string(&value).unwrap();
+            assert_eq!(
+                written,
+                "{\"a\":[1,-2.5,1e+20,1.5e-7,\"x\\ny\",true,false,null],\"b\":{},\"c\":[]}"
+            );
+            assert!(parser.parse_str(&written).is_ok());
+        }
+    }
+
+    #[test]
+    fn should_only_write_non_finite_numbers_when_enabled() {
+        let input = "{\"stats\" : [1.5, NaN], \"range\" : [-Infinity, Infinity]}";
+        let options = ParserOptions::default()
+            .with_numeric_mode(NumericMode::Mixed)
+            .with_non_finite_numbers(true);
+        let parser = Parser::with_options(options.clone());
+        let value = parser.parse_str(input).unwrap();
+
+        let err = Writer::default().write_string(&value).unwrap_err();
+        assert_eq!(err.source, ParserErrorSource::Writer);
+        assert_eq!(
+            err.details,
+            ParserErrorDetails::ExtensionRequired(Extension::NonFiniteNumbers)
+        );
+        assert_eq!(err.pointer.unwrap().as_str(), "/stats/1");
+
+        let expected = "{\"stats\":[1.5,NaN],\"range\":[-Infinity,Infinity]}";
+        for writer in [
+            Writer::default().with_non_finite_numbers(true),
+            Writer::with_options(&options),
+            Writer::with_options(&ParserOptions::default().with_dialect(Dialect::Json5)),
+        ] {
+            assert_eq!(writer.write_string(&value).unwrap(), expected);
+        }
+        assert!(Writer::with_options(&ParserOptions::default())
+            .write_string(&JsonValue::Float(f64::INFINITY))
+            .is_err());
+    }
+
+    #[test]
+    fn should_reject_invalid_placeholders() {
+        let value = JsonValue::Array(vec![JsonValue::Null, JsonValue::Invalid]);
+        let err = Writer::default().write_string(&value).unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::InvalidValue);
+        assert_eq!(err.pointer.unwrap().as_str(), "/1");
+    }
+
+    #[test]
+    fn should_report_stream_failures() {
+        struct Failing;
+        impl io::Write for Failing {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::Other, "closed"))
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let err = Writer::default()
+            .write(&JsonValue::Null, &mut Failing)
+            .unwrap_err();
+        assert_eq!(err.details, ParserErrorDetails::StreamFailure);
+        assert!(err.cause.is_some());
+    }
+}